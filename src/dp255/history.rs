@@ -0,0 +1,261 @@
+// /r/dailyprogrammer, #255, <weldale@gmail.com>
+//
+// Keeps every toggle instruction applied to a room so that the state after
+// any step can be queried without replaying all instructions from the start.
+
+use std::ops::Range;
+use std::str::FromStr;
+
+use super::{
+    Room,
+    normalize_range,
+    range_from_str
+};
+
+const DEFAULT_CHECKPOINT_INTERVAL: usize = 64;
+
+#[derive(Debug)]
+pub struct RoomHistory {
+    room: Room,
+    steps: Vec<Range<usize>>,
+    lit_counts: Vec<usize>,
+    checkpoints: Vec<Vec<bool>>,
+    checkpoint_interval: usize
+}
+
+impl RoomHistory {
+
+    pub fn new(size: usize) -> RoomHistory {
+        RoomHistory::with_checkpoint_interval(size, DEFAULT_CHECKPOINT_INTERVAL)
+    }
+
+    // every `interval` steps a full copy of the switches is kept,
+    // a query has to replay at most `interval - 1` steps on top of it
+    pub fn with_checkpoint_interval(size: usize, interval: usize) -> RoomHistory {
+        assert!(interval > 0);
+
+        let room = Room::new( size );
+        let initial = room.switches.clone();
+
+        RoomHistory {
+            room,
+            steps: Vec::new(),
+            lit_counts: vec![0],
+            checkpoints: vec![initial],
+            checkpoint_interval: interval
+        }
+    }
+
+    pub fn toggle_range(&mut self, range: Range<usize>) {
+        let mut lit_count = self.get_enabled_light_count();
+
+        for i in normalize_range(range.clone()) {
+            let switch = &mut self.room.switches[i];
+            *switch = !*switch;
+
+            if *switch {
+                lit_count += 1;
+            } else {
+                lit_count -= 1;
+            }
+        }
+
+        self.steps.push(range);
+        self.lit_counts.push(lit_count);
+
+        if self.steps.len().is_multiple_of(self.checkpoint_interval) {
+            self.checkpoints.push(self.room.switches.clone());
+        }
+    }
+
+    // toggling is its own inverse, so undoing a step applies its range again
+    pub fn undo(&mut self, n: usize) -> Vec<Range<usize>> {
+        let keep = if n > self.steps.len() { 0 } else { self.steps.len() - n };
+
+        let undone = self.steps.split_off(keep);
+
+        for range in undone.iter().rev() {
+            self.room.toggle_range(range.clone());
+        }
+
+        self.lit_counts.truncate(keep + 1);
+        self.checkpoints.truncate(keep / self.checkpoint_interval + 1);
+
+        undone
+    }
+
+    pub fn get_step_count(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn get_steps(&self) -> &[Range<usize>] {
+        &self.steps
+    }
+
+    pub fn get_room(&self) -> &Room {
+        &self.room
+    }
+
+    pub fn get_enabled_light_count(&self) -> usize {
+        self.lit_counts[self.steps.len()]
+    }
+
+    // step 0 is the initial room with all lights off
+    pub fn get_enabled_light_count_after(&self, step: usize) -> Option<usize> {
+        self.lit_counts.get(step).cloned()
+    }
+
+    pub fn get_switch_after(&self, switch: usize, step: usize) -> Option<bool> {
+        if step > self.steps.len() || switch >= self.room.num_switches {
+            return None;
+        }
+
+        let checkpoint = step / self.checkpoint_interval;
+        let mut state = self.checkpoints[checkpoint][switch];
+
+        for range in &self.steps[checkpoint * self.checkpoint_interval .. step] {
+            let actual_range = normalize_range(range.clone());
+
+            if actual_range.start <= switch && switch < actual_range.end {
+                state = !state;
+            }
+        }
+
+        Some(state)
+    }
+
+    pub fn get_room_after(&self, step: usize) -> Option<Room> {
+        if step > self.steps.len() {
+            return None;
+        }
+
+        let checkpoint = step / self.checkpoint_interval;
+
+        let mut room = Room {
+            num_switches: self.room.num_switches,
+            switches: self.checkpoints[checkpoint].clone()
+        };
+
+        for range in &self.steps[checkpoint * self.checkpoint_interval .. step] {
+            room.toggle_range(range.clone());
+        }
+
+        Some(room)
+    }
+}
+
+impl FromStr for RoomHistory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<RoomHistory, String> {
+        let mut line_iter = s.lines();
+        let size_str = line_iter.next().ok_or("missing # lightswitches")?;
+        let size = size_str.parse::<usize>().map_err(|err| err.to_string())?;
+
+        let mut history = RoomHistory::new( size );
+
+        for line in line_iter {
+            let range = range_from_str( line )?;
+            history.toggle_range( range );
+        }
+
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::ops::Range;
+    use std::str::FromStr;
+    use dp255::Room;
+    use dp255::history::*;
+
+    const SIMPLE: &str = "10\n\
+3 6\n\
+0 4\n\
+7 3\n\
+9 9";
+
+    #[test]
+    fn test_lit_count_after() {
+        let history = RoomHistory::from_str(SIMPLE).unwrap();
+
+        assert_eq!( 4, history.get_step_count() );
+        assert_eq!( Some(0), history.get_enabled_light_count_after(0) );
+        assert_eq!( Some(4), history.get_enabled_light_count_after(1) );
+        assert_eq!( Some(5), history.get_enabled_light_count_after(2) );
+        assert_eq!( Some(6), history.get_enabled_light_count_after(3) );
+        assert_eq!( Some(7), history.get_enabled_light_count_after(4) );
+        assert_eq!( None, history.get_enabled_light_count_after(5) );
+
+        assert_eq!( 7, history.get_room().get_enabled_light_count() );
+    }
+
+    #[test]
+    fn test_switch_after() {
+        let mut history = RoomHistory::with_checkpoint_interval(10, 2);
+
+        history.toggle_range(3..6);
+        history.toggle_range(0..4);
+        history.toggle_range(Range { start: 7, end: 3 });
+        history.toggle_range(9..9);
+
+        assert_eq!( Some(false), history.get_switch_after(3, 0) );
+        assert_eq!( Some(true), history.get_switch_after(3, 1) );
+        assert_eq!( Some(false), history.get_switch_after(3, 2) );
+        assert_eq!( Some(true), history.get_switch_after(3, 3) );
+        assert_eq!( Some(true), history.get_switch_after(9, 4) );
+        assert_eq!( None, history.get_switch_after(10, 4) );
+        assert_eq!( None, history.get_switch_after(0, 5) );
+    }
+
+    #[test]
+    fn test_matches_replay() {
+        let input = include_str!("../../data/normal.txt");
+        let history = RoomHistory::from_str(input).unwrap();
+
+        let lines: Vec<&str> = input.lines().collect();
+
+        for step in 0 .. history.get_step_count() + 1 {
+            let replayed = Room::from_str(&lines[.. step + 1].join("\n")).unwrap();
+            let room = history.get_room_after(step).unwrap();
+
+            assert_eq!( replayed.switches, room.switches );
+            assert_eq!( Some(replayed.get_enabled_light_count()), history.get_enabled_light_count_after(step) );
+
+            for switch in 0 .. replayed.num_switches {
+                assert_eq!( Some(replayed.switches[switch]), history.get_switch_after(switch, step) );
+            }
+        }
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut history = RoomHistory::with_checkpoint_interval(10, 2);
+
+        history.toggle_range(3..6);
+        history.toggle_range(0..4);
+        history.toggle_range(Range { start: 7, end: 3 });
+        history.toggle_range(9..9);
+
+        let undone = history.undo(3);
+
+        assert_eq!( vec![0..4, Range { start: 7, end: 3 }, 9..9], undone );
+        assert_eq!( 1, history.get_step_count() );
+        assert_eq!( 4, history.get_enabled_light_count() );
+        assert_eq!( 4, history.get_room().get_enabled_light_count() );
+
+        history.toggle_range(0..9);
+
+        assert_eq!( 6, history.get_enabled_light_count() );
+        assert_eq!( Some(false), history.get_switch_after(4, 2) );
+        assert_eq!( Some(true), history.get_switch_after(7, 2) );
+
+        history.undo(10);
+
+        assert_eq!( 0, history.get_step_count() );
+        assert_eq!( 0, history.get_room().get_enabled_light_count() );
+    }
+
+}
@@ -1,5 +1,8 @@
 // /r/dailyprogrammer, #255, <weldale@gmail.com>
 
+pub mod history;
+pub mod solver;
+
 use std::ops::Range;
 use std::path::Path;
 use std::fs::File;
@@ -57,25 +60,39 @@ impl Room {
         }
     }
 
-    pub fn toggle_range(&mut self, range: Range<usize>) {
-        let actual_start = if range.start > range.end { range.end } else { range.start };
-        let actual_end = if range.start > range.end { range.start + 1 } else { range.end + 1 };
-
-        let actual_range = Range {
-            start: actual_start,
-            end: actual_end
-        };
+    pub fn from_switches(switches: Vec<bool>) -> Room {
+        Room {
+            num_switches: switches.len(),
+            switches
+        }
+    }
 
-        for i in actual_range {
+    pub fn toggle_range(&mut self, range: Range<usize>) {
+        for i in normalize_range(range) {
             self.switches[i] = !self.switches[i];
         }
     }
 
+    pub fn get_switches(&self) -> &[bool] {
+        &self.switches
+    }
+
     pub fn get_enabled_light_count(&self) -> usize {
         self.switches.iter().filter( |&&p| p == true ).count()
     }
 }
 
+// the input ranges are inclusive and may be given in either direction
+fn normalize_range(range: Range<usize>) -> Range<usize> {
+    let actual_start = if range.start > range.end { range.end } else { range.start };
+    let actual_end = if range.start > range.end { range.start + 1 } else { range.end + 1 };
+
+    Range {
+        start: actual_start,
+        end: actual_end
+    }
+}
+
 fn range_from_str<T: ToString>(s: T) -> Result<Range<usize>, String> {
     let string: String = s.to_string();
     let v: Vec<&str> = string.trim().split(' ').collect();
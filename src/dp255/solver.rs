@@ -0,0 +1,135 @@
+// /r/dailyprogrammer, #255, <weldale@gmail.com>
+//
+// The inverse problem: find the fewest range toggles that turn an all-off
+// room into a given pattern.
+//
+// A toggle of the inclusive range a..b flips exactly two entries of the
+// difference representation edge[i] = switch[i-1] ^ switch[i] (with the
+// room padded by an off switch on both sides): edge[a] and edge[b + 1].
+// Every toggle can remove at most two edges, so pairing the edges of the
+// target in order is optimal.
+
+use std::fmt::Write;
+use std::ops::Range;
+
+use super::Room;
+
+pub fn edges(switches: &[bool]) -> Vec<usize> {
+    let mut result = Vec::new();
+    let mut previous = false;
+
+    for (i, &switch) in switches.iter().enumerate() {
+        if switch != previous {
+            result.push(i);
+        }
+        previous = switch;
+    }
+
+    if previous {
+        result.push(switches.len());
+    }
+
+    result
+}
+
+// the returned ranges use the input format's inclusive end
+pub fn minimal_toggles(target: &Room) -> Vec<Range<usize>> {
+    edges(target.get_switches())
+        .chunks(2)
+        .map(|pair| Range { start: pair[0], end: pair[1] - 1 })
+        .collect()
+}
+
+pub fn to_instructions(num_switches: usize, toggles: &[Range<usize>]) -> String {
+    let mut result = String::new();
+
+    writeln!(result, "{}", num_switches).unwrap();
+
+    for toggle in toggles {
+        writeln!(result, "{} {}", toggle.start, toggle.end).unwrap();
+    }
+
+    result
+}
+
+// returns the instructions in the challenge's input format after checking
+// that replaying them yields the target again
+pub fn solve(target: &Room) -> Result<String, String> {
+    let toggles = minimal_toggles(target);
+    let instructions = to_instructions(target.num_switches, &toggles);
+
+    let replayed = Room::from_str(&instructions)?;
+
+    if replayed.switches != target.switches {
+        return Err(format!("instructions do not reproduce the target:\n{}", instructions));
+    }
+
+    Ok(instructions)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::ops::Range;
+    use dp255::Room;
+    use dp255::solver::*;
+
+    fn pattern(s: &str) -> Room {
+        Room::from_switches(s.chars().map(|c| c == '1').collect())
+    }
+
+    #[test]
+    fn test_edges() {
+        assert_eq!( Vec::<usize>::new(), edges(&[false, false]) );
+        assert_eq!( vec![0, 2], edges(&[true, true, false]) );
+        assert_eq!( vec![1, 2, 3, 4], edges(&[false, true, false, true]) );
+    }
+
+    #[test]
+    fn test_minimal_toggles() {
+        assert_eq!( Vec::<Range<usize>>::new(), minimal_toggles(&pattern("0000")) );
+        assert_eq!( vec![Range { start: 0, end: 3 }], minimal_toggles(&pattern("1111")) );
+        assert_eq!( vec![Range { start: 1, end: 2 }, Range { start: 4, end: 4 }], minimal_toggles(&pattern("01101")) );
+    }
+
+    #[test]
+    fn test_solve_round_trip() {
+        let target = Room::from_str("10\n\
+3 6\n\
+0 4\n\
+7 3\n\
+9 9").unwrap();
+
+        let instructions = solve(&target).unwrap();
+
+        assert_eq!( "10\n0 4\n7 7\n9 9\n", instructions );
+
+        let replayed = Room::from_str(&instructions).unwrap();
+
+        assert_eq!( target.get_switches(), replayed.get_switches() );
+    }
+
+    #[test]
+    fn test_solve_all_patterns() {
+        for size in 0 .. 8 {
+            for bits in 0 .. 1 << size {
+                let target = Room::from_switches((0 .. size).map(|i| bits & (1 << i) != 0).collect());
+
+                let instructions = solve(&target).unwrap();
+                let toggle_count = instructions.lines().count() - 1;
+
+                assert_eq!( edges(target.get_switches()).len() / 2, toggle_count );
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_file() {
+        let target = Room::from_path("data/normal.txt").unwrap();
+
+        let instructions = solve(&target).unwrap();
+
+        assert!( instructions.lines().count() <= 25 );
+    }
+
+}
@@ -0,0 +1,158 @@
+// Double-buffered cellular automaton on a flat grid.
+//
+// Every cell of the next generation is computed by the rule from the cell
+// and the values of its neighbors, then the buffers are swapped.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{
+  Hash,
+  Hasher
+};
+
+use super::Flat2dArray;
+use super::neighborhood::{
+  Bounds,
+  Neighborhood,
+  neighbors
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cycle {
+  // first generation that is repeated later on
+  pub start: usize,
+  pub period: usize
+}
+
+pub struct Automaton<T, F> {
+  current: Flat2dArray<T>,
+  next: Flat2dArray<T>,
+  neighborhood: Neighborhood,
+  bounds: Bounds,
+  rule: F,
+  generation: usize,
+  buffer: Vec<T>
+}
+
+impl<T, F> Automaton<T, F>
+where T: Clone, F: FnMut(&T, &[T]) -> T {
+  pub fn new(grid: Flat2dArray<T>, neighborhood: Neighborhood, bounds: Bounds, rule: F) -> Automaton<T, F> {
+    Automaton {
+      next: grid.clone(),
+      current: grid,
+      neighborhood,
+      bounds,
+      rule,
+      generation: 0,
+      buffer: Vec::with_capacity(8)
+    }
+  }
+
+  pub fn get_grid(&self) -> &Flat2dArray<T> {
+    &self.current
+  }
+
+  pub fn into_grid(self) -> Flat2dArray<T> {
+    self.current
+  }
+
+  pub fn get_generation(&self) -> usize {
+    self.generation
+  }
+
+  pub fn step(&mut self) {
+    let width = self.current.get_width();
+    let height = self.current.get_height();
+
+    for y in 0..height {
+      for x in 0..width {
+        self.buffer.clear();
+
+        for pos in neighbors(width, height, (x, y), self.neighborhood, self.bounds) {
+          self.buffer.push(self.current[pos].clone());
+        }
+
+        self.next[(x, y)] = (self.rule)(&self.current[(x, y)], &self.buffer);
+      }
+    }
+
+    std::mem::swap(&mut self.current, &mut self.next);
+    self.generation += 1;
+  }
+
+  pub fn run(&mut self, generations: usize) {
+    for _ in 0..generations {
+      self.step();
+    }
+  }
+}
+
+fn state_hash<T: Hash>(grid: &Flat2dArray<T>) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  grid.as_slice().hash(&mut hasher);
+  hasher.finish()
+}
+
+impl<T, F> Automaton<T, F>
+where T: Clone + Hash, F: FnMut(&T, &[T]) -> T {
+  // steps until a state repeats or `max_generations` have passed; states are
+  // only compared by their hash, so a collision can report a false cycle
+  pub fn find_cycle(&mut self, max_generations: usize) -> Option<Cycle> {
+    let mut seen = HashMap::new();
+    seen.insert(state_hash(&self.current), self.generation);
+
+    for _ in 0..max_generations {
+      self.step();
+
+      if let Some(&start) = seen.get(&state_hash(&self.current)) {
+        return Some(Cycle {
+          start,
+          period: self.generation - start
+        });
+      }
+
+      seen.insert(state_hash(&self.current), self.generation);
+    }
+
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use dp256::*;
+  use dp256::automaton::*;
+  use dp256::neighborhood::*;
+
+  #[test]
+  fn test_step() {
+    // every cell becomes the sum of its orthogonal neighbors
+    let grid = Flat2dArray::from(vec![vec![0, 0, 0], vec![0, 1, 0], vec![0, 0, 0]]);
+    let mut automaton = Automaton::new(grid, Neighborhood::VonNeumann, Bounds::Clip, |_: &u32, n: &[u32]| n.iter().sum());
+
+    automaton.step();
+
+    assert_eq!(1, automaton.get_generation());
+    assert_eq!(&Flat2dArray::from(vec![vec![0, 1, 0], vec![1, 0, 1], vec![0, 1, 0]]), automaton.get_grid());
+  }
+
+  #[test]
+  fn test_find_cycle() {
+    // a counter modulo 5 on every cell
+    let grid: Flat2dArray<u8> = Flat2dArray::new(2, 2, 0);
+    let mut automaton = Automaton::new(grid, Neighborhood::Moore, Bounds::Torus, |&c: &u8, _: &[u8]| (c + 1) % 5);
+
+    assert_eq!(Some(Cycle { start: 0, period: 5 }), automaton.find_cycle(100));
+
+    let grid: Flat2dArray<u8> = Flat2dArray::new(2, 2, 0);
+    let mut automaton = Automaton::new(grid, Neighborhood::Moore, Bounds::Torus, |&c: &u8, _: &[u8]| std::cmp::min(c + 1, 3));
+
+    assert_eq!(Some(Cycle { start: 3, period: 1 }), automaton.find_cycle(100));
+
+    let grid: Flat2dArray<u8> = Flat2dArray::new(2, 2, 0);
+    let mut automaton = Automaton::new(grid, Neighborhood::Moore, Bounds::Torus, |&c: &u8, _: &[u8]| (c + 1) % 5);
+
+    assert_eq!(None, automaton.find_cycle(4));
+  }
+
+}
@@ -0,0 +1,464 @@
+// Import and export of grids as Netpbm images (PGM/PPM) and CSV.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{
+  Read,
+  Write
+};
+use std::path::Path;
+use std::str::FromStr;
+
+use super::Flat2dArray;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PnmFormat {
+  Ascii,
+  Binary
+}
+
+pub trait PnmPixel: Sized {
+  const ASCII_MAGIC: &'static str;
+  const BINARY_MAGIC: &'static str;
+  const CHANNELS: usize;
+
+  fn channels(&self) -> Vec<u8>;
+
+  fn from_channels(channels: &[u8]) -> Self;
+}
+
+// grayscale, PGM
+impl PnmPixel for u8 {
+  const ASCII_MAGIC: &'static str = "P2";
+  const BINARY_MAGIC: &'static str = "P5";
+  const CHANNELS: usize = 1;
+
+  fn channels(&self) -> Vec<u8> {
+    vec![*self]
+  }
+
+  fn from_channels(channels: &[u8]) -> u8 {
+    channels[0]
+  }
+}
+
+// rgb, PPM
+impl PnmPixel for [u8; 3] {
+  const ASCII_MAGIC: &'static str = "P3";
+  const BINARY_MAGIC: &'static str = "P6";
+  const CHANNELS: usize = 3;
+
+  fn channels(&self) -> Vec<u8> {
+    self.to_vec()
+  }
+
+  fn from_channels(channels: &[u8]) -> [u8; 3] {
+    [channels[0], channels[1], channels[2]]
+  }
+}
+
+pub fn write_pnm<P, W>(array: &Flat2dArray<P>, w: &mut W, format: PnmFormat) -> Result<(), String>
+where P: PnmPixel, W: Write {
+  let magic = match format {
+    PnmFormat::Ascii => P::ASCII_MAGIC,
+    PnmFormat::Binary => P::BINARY_MAGIC
+  };
+
+  let mut out: Vec<u8> = format!("{}\n{} {}\n255\n", magic, array.get_width(), array.get_height()).into_bytes();
+
+  match format {
+    PnmFormat::Ascii => {
+      for row in array.rows() {
+        let samples: Vec<String> = row.iter()
+          .flat_map(|pixel| pixel.channels())
+          .map(|sample| sample.to_string())
+          .collect();
+
+        out.extend(samples.join(" ").into_bytes());
+        out.push(b'\n');
+      }
+    },
+    PnmFormat::Binary => {
+      for pixel in array.iter() {
+        out.extend(pixel.channels());
+      }
+    }
+  }
+
+  w.write_all(&out).map_err(|err| err.to_string())
+}
+
+struct PnmReader<'a> {
+  bytes: &'a [u8],
+  pos: usize
+}
+
+impl<'a> PnmReader<'a> {
+  fn skip_whitespace_and_comments(&mut self) {
+    while self.pos < self.bytes.len() {
+      let byte = self.bytes[self.pos];
+
+      if byte == b'#' {
+        while self.pos < self.bytes.len() && self.bytes[self.pos] != b'\n' {
+          self.pos += 1;
+        }
+      } else if byte.is_ascii_whitespace() {
+        self.pos += 1;
+      } else {
+        break;
+      }
+    }
+  }
+
+  fn token(&mut self, what: &str) -> Result<&'a str, String> {
+    self.skip_whitespace_and_comments();
+
+    let start = self.pos;
+
+    while self.pos < self.bytes.len() && !self.bytes[self.pos].is_ascii_whitespace() && self.bytes[self.pos] != b'#' {
+      self.pos += 1;
+    }
+
+    if start == self.pos {
+      return Err(format!("unexpected end of data, missing {}", what));
+    }
+
+    std::str::from_utf8(&self.bytes[start..self.pos]).map_err(|_| format!("{} is not valid text", what))
+  }
+
+  fn number(&mut self, what: &str) -> Result<usize, String> {
+    let token = self.token(what)?;
+
+    token.parse::<usize>().map_err(|_| format!("{} '{}' is not a number", what, token))
+  }
+}
+
+// samples are stretched to 0..=255, `write_pnm` always writes a maxval of 255
+fn scale(sample: usize, maxval: usize) -> u8 {
+  ((sample * 255 + maxval / 2) / maxval) as u8
+}
+
+pub fn read_pnm<P, R>(r: &mut R) -> Result<Flat2dArray<P>, String>
+where P: PnmPixel, R: Read {
+  let mut bytes = Vec::new();
+  r.read_to_end(&mut bytes).map_err(|err| err.to_string())?;
+
+  let mut reader = PnmReader { bytes: &bytes, pos: 0 };
+
+  let magic = reader.token("magic number")?;
+
+  let format = if magic == P::ASCII_MAGIC {
+    PnmFormat::Ascii
+  } else if magic == P::BINARY_MAGIC {
+    PnmFormat::Binary
+  } else {
+    return Err(format!("invalid magic number '{}', expected {} or {}", magic, P::ASCII_MAGIC, P::BINARY_MAGIC));
+  };
+
+  let width = reader.number("width")?;
+  let height = reader.number("height")?;
+  let maxval = reader.number("maxval")?;
+
+  if maxval == 0 || maxval > 255 {
+    return Err(format!("maxval {} is not supported, expected 1 to 255", maxval));
+  }
+
+  let sample_count = width.checked_mul(height)
+    .and_then(|pixels| pixels.checked_mul(P::CHANNELS))
+    .ok_or(format!("{}x{} is too large", width, height))?;
+
+  // every sample takes at least one byte, checked before anything is allocated
+  let remaining = bytes.len() - reader.pos;
+
+  if sample_count > remaining {
+    return Err(format!("expected {} samples for {}x{}, only {} bytes left", sample_count, width, height, remaining));
+  }
+
+  let mut samples: Vec<u8> = Vec::with_capacity(sample_count);
+
+  match format {
+    PnmFormat::Ascii => {
+      for i in 0..sample_count {
+        let token = reader.token("sample").map_err(|_| format!("expected {} samples, got {}", sample_count, i))?;
+        let sample = token.parse::<usize>().map_err(|_| format!("sample {} '{}' is not a number", i, token))?;
+
+        if sample > maxval {
+          return Err(format!("sample {} is {}, exceeds maxval {}", i, sample, maxval));
+        }
+
+        samples.push(scale(sample, maxval));
+      }
+
+      reader.skip_whitespace_and_comments();
+
+      if reader.pos < bytes.len() {
+        return Err(format!("expected {} samples, got more", sample_count));
+      }
+    },
+    PnmFormat::Binary => {
+      // exactly one whitespace byte separates the header from the raster
+      if !bytes.get(reader.pos).is_some_and(|byte| byte.is_ascii_whitespace()) {
+        return Err("expected whitespace after maxval".to_string());
+      }
+
+      let start = reader.pos + 1;
+      let available = bytes.len() - start;
+
+      if available != sample_count {
+        return Err(format!("expected {} bytes of pixel data for {}x{}, got {}", sample_count, width, height, available));
+      }
+
+      for (i, &sample) in bytes[start..].iter().enumerate() {
+        if sample as usize > maxval {
+          return Err(format!("sample {} is {}, exceeds maxval {}", i, sample, maxval));
+        }
+      }
+
+      samples.extend(bytes[start..].iter().map(|&sample| scale(sample as usize, maxval)));
+    }
+  }
+
+  let pixels: Vec<P> = samples.chunks(P::CHANNELS).map(P::from_channels).collect();
+
+  Flat2dArray::from_vec(width, height, pixels)
+}
+
+pub fn save_pnm<P, Q>(array: &Flat2dArray<P>, path: Q, format: PnmFormat) -> Result<(), String>
+where P: PnmPixel, Q: AsRef<Path> {
+  let mut file = File::create(path).map_err(|err| err.to_string())?;
+
+  write_pnm(array, &mut file, format)
+}
+
+pub fn load_pnm<P, Q>(path: Q) -> Result<Flat2dArray<P>, String>
+where P: PnmPixel, Q: AsRef<Path> {
+  let mut file = File::open(path).map_err(|err| err.to_string())?;
+
+  read_pnm(&mut file)
+}
+
+fn csv_field(value: &str) -> String {
+  if value.contains(',') || value.contains('"') || value.contains('\n') || value.contains('\r') {
+    format!("\"{}\"", value.replace('"', "\"\""))
+  } else {
+    value.to_string()
+  }
+}
+
+pub fn write_csv<T, W>(array: &Flat2dArray<T>, w: &mut W) -> Result<(), String>
+where T: fmt::Display, W: Write {
+  let mut out = String::new();
+
+  for row in array.rows() {
+    let fields: Vec<String> = row.iter().map(|value| csv_field(&value.to_string())).collect();
+
+    out.push_str(&fields.join(","));
+    out.push('\n');
+  }
+
+  w.write_all(out.as_bytes()).map_err(|err| err.to_string())
+}
+
+fn split_csv(s: &str) -> Result<Vec<Vec<String>>, String> {
+  let mut rows: Vec<Vec<String>> = Vec::new();
+  let mut row: Vec<String> = Vec::new();
+  let mut field = String::new();
+  let mut in_quotes = false;
+  let mut chars = s.chars().peekable();
+  let mut line = 1;
+  let mut quote_line = 1;
+
+  while let Some(c) = chars.next() {
+    if in_quotes {
+      match c {
+        '"' if chars.peek() == Some(&'"') => {
+          chars.next();
+          field.push('"');
+        },
+        '"' => in_quotes = false,
+        _ => {
+          if c == '\n' {
+            line += 1;
+          }
+          field.push(c);
+        }
+      }
+
+      continue;
+    }
+
+    match c {
+      '"' if field.is_empty() => {
+        in_quotes = true;
+        quote_line = line;
+      },
+      '"' => return Err(format!("line {}: unexpected quote inside an unquoted field", line)),
+      ',' => row.push(std::mem::take(&mut field)),
+      '\r' => {},
+      '\n' => {
+        row.push(std::mem::take(&mut field));
+        rows.push(std::mem::take(&mut row));
+        line += 1;
+      },
+      _ => field.push(c)
+    }
+  }
+
+  if in_quotes {
+    return Err(format!("line {}: unterminated quoted field", quote_line));
+  }
+
+  if !field.is_empty() || !row.is_empty() {
+    row.push(field);
+    rows.push(row);
+  }
+
+  Ok(rows)
+}
+
+pub fn read_csv<T, R>(r: &mut R) -> Result<Flat2dArray<T>, String>
+where T: FromStr, T::Err: fmt::Display, R: Read {
+  let mut s = String::new();
+  r.read_to_string(&mut s).map_err(|err| err.to_string())?;
+
+  let rows = split_csv(&s)?;
+
+  let width = rows.first().map(|row| row.len()).unwrap_or(0);
+  let height = rows.len();
+
+  let mut data = Vec::with_capacity(width * height);
+
+  for (y, row) in rows.into_iter().enumerate() {
+    if row.len() != width {
+      return Err(format!("row {} has {} fields, expected {}", y + 1, row.len(), width));
+    }
+
+    for (x, field) in row.into_iter().enumerate() {
+      let value = field.parse::<T>().map_err(|err| format!("row {}, field {}: {}", y + 1, x + 1, err))?;
+      data.push(value);
+    }
+  }
+
+  Flat2dArray::from_vec(width, height, data)
+}
+
+#[cfg(test)]
+mod tests {
+  use dp256::*;
+  use dp256::image::*;
+
+  fn gradient() -> Flat2dArray<u8> {
+    Flat2dArray::from_fn(4, 3, |x, y| (x * 60 + y) as u8)
+  }
+
+  fn colors() -> Flat2dArray<[u8; 3]> {
+    Flat2dArray::from_fn(3, 2, |x, y| [x as u8 * 100, y as u8 * 200, 7])
+  }
+
+  #[test]
+  fn test_pgm_round_trip() {
+    for &format in &[PnmFormat::Ascii, PnmFormat::Binary] {
+      let mut bytes: Vec<u8> = Vec::new();
+      write_pnm(&gradient(), &mut bytes, format).unwrap();
+
+      let read: Flat2dArray<u8> = read_pnm(&mut &bytes[..]).unwrap();
+
+      assert_eq!(gradient(), read);
+    }
+  }
+
+  #[test]
+  fn test_ppm_round_trip() {
+    for &format in &[PnmFormat::Ascii, PnmFormat::Binary] {
+      let mut bytes: Vec<u8> = Vec::new();
+      write_pnm(&colors(), &mut bytes, format).unwrap();
+
+      let read: Flat2dArray<[u8; 3]> = read_pnm(&mut &bytes[..]).unwrap();
+
+      assert_eq!(colors(), read);
+    }
+  }
+
+  #[test]
+  fn test_pgm_ascii_output() {
+    let mut bytes: Vec<u8> = Vec::new();
+    write_pnm(&Flat2dArray::from(vec![vec![0u8, 255], vec![7, 8]]), &mut bytes, PnmFormat::Ascii).unwrap();
+
+    assert_eq!("P2\n2 2\n255\n0 255\n7 8\n", String::from_utf8(bytes).unwrap());
+  }
+
+  #[test]
+  fn test_pnm_comments() {
+    let read: Flat2dArray<u8> = read_pnm(&mut &b"P2 # gray\n# size\n2 1\n15 # max\n3 15\n"[..]).unwrap();
+
+    assert_eq!(vec![51, 255], read.into_vec());
+  }
+
+  #[test]
+  fn test_pnm_maxval_round_trip() {
+    for input in &[&b"P2\n3 1\n15\n0 7 15\n"[..], &b"P5\n3 1\n15\n\x00\x07\x0f"[..]] {
+      let read: Flat2dArray<u8> = read_pnm(&mut &input[..]).unwrap();
+      assert_eq!(vec![0, 119, 255], read.clone().into_vec());
+
+      let mut bytes: Vec<u8> = Vec::new();
+      write_pnm(&read, &mut bytes, PnmFormat::Ascii).unwrap();
+
+      assert_eq!("P2\n3 1\n255\n0 119 255\n", String::from_utf8(bytes.clone()).unwrap());
+      assert_eq!(read, read_pnm(&mut &bytes[..]).unwrap());
+    }
+  }
+
+  #[test]
+  fn test_pnm_errors() {
+    fn err(input: &[u8]) -> String {
+      read_pnm::<u8, _>(&mut &input[..]).unwrap_err()
+    }
+
+    assert_eq!("invalid magic number 'P3', expected P2 or P5", err(b"P3\n1 1\n255\n0 0 0\n"));
+    assert_eq!("unexpected end of data, missing height", err(b"P2\n1"));
+    assert_eq!("width 'x' is not a number", err(b"P2\nx 1\n255\n0\n"));
+    assert_eq!("maxval 1000 is not supported, expected 1 to 255", err(b"P2\n1 1\n1000\n0\n"));
+    assert_eq!("expected 4 samples, got 3", err(b"P2\n2 2\n255\n1 2 3\n"));
+    assert_eq!("expected 1 samples, got more", err(b"P2\n1 1\n255\n1 2\n"));
+    assert_eq!("sample 0 is 20, exceeds maxval 15", err(b"P2\n1 1\n15\n20\n"));
+    assert_eq!("expected 4 bytes of pixel data for 2x2, got 3", err(b"P5\n2 2\n255\n\x01\x02\x03"));
+    assert_eq!("expected whitespace after maxval", err(b"P5\n0 0\n255"));
+    assert_eq!("expected 16000000000000000000 samples for 4000000000x4000000000, only 2 bytes left", err(b"P2\n4000000000 4000000000\n255\n0"));
+    assert_eq!("18446744073709551615x2 is too large", err(b"P2\n18446744073709551615 2\n255\n0"));
+  }
+
+  #[test]
+  fn test_csv_round_trip() {
+    let array = construct_regular_matrix(4, 3);
+
+    let mut bytes: Vec<u8> = Vec::new();
+    write_csv(&array, &mut bytes).unwrap();
+
+    assert_eq!("0,1,2,3\n4,5,6,7\n8,9,10,11\n", String::from_utf8(bytes.clone()).unwrap());
+
+    let read: Flat2dArray<usize> = read_csv(&mut &bytes[..]).unwrap();
+    assert_eq!(array, read);
+  }
+
+  #[test]
+  fn test_csv_quoting() {
+    let array = Flat2dArray::from(vec![
+      vec!["a,b".to_string(), "say \"hi\"".to_string()],
+      vec!["two\nlines".to_string(), "".to_string()]
+    ]);
+
+    let mut bytes: Vec<u8> = Vec::new();
+    write_csv(&array, &mut bytes).unwrap();
+
+    let read: Flat2dArray<String> = read_csv(&mut &bytes[..]).unwrap();
+    assert_eq!(array, read);
+  }
+
+  #[test]
+  fn test_csv_errors() {
+    assert_eq!("row 2 has 1 fields, expected 2", read_csv::<usize, _>(&mut &b"1,2\n3\n"[..]).unwrap_err());
+    assert_eq!("row 1, field 2: invalid digit found in string", read_csv::<usize, _>(&mut &b"1,x\n"[..]).unwrap_err());
+    assert_eq!("line 1: unterminated quoted field", read_csv::<String, _>(&mut &b"\"abc\n"[..]).unwrap_err());
+  }
+
+}
@@ -0,0 +1,366 @@
+// Life-like automata in B/S rule notation and the RLE pattern format.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::Flat2dArray;
+use super::automaton::Automaton;
+use super::neighborhood::{
+  Bounds,
+  Neighborhood
+};
+
+// the neighbor counts under which a dead cell is born and a live cell survives
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LifeRule {
+  birth: [bool; 9],
+  survival: [bool; 9]
+}
+
+impl LifeRule {
+  pub fn new(birth: &[usize], survival: &[usize]) -> Result<LifeRule, String> {
+    let mut rule = LifeRule {
+      birth: [false; 9],
+      survival: [false; 9]
+    };
+
+    for &count in birth.iter().chain(survival.iter()) {
+      if count > 8 {
+        return Err(format!("neighbor count out of range: {}", count));
+      }
+    }
+
+    for &count in birth {
+      rule.birth[count] = true;
+    }
+
+    for &count in survival {
+      rule.survival[count] = true;
+    }
+
+    Ok(rule)
+  }
+
+  pub fn conway() -> LifeRule {
+    LifeRule::new(&[3], &[2, 3]).unwrap()
+  }
+
+  pub fn next_state(&self, alive: bool, alive_neighbors: usize) -> bool {
+    if alive {
+      self.survival[alive_neighbors]
+    } else {
+      self.birth[alive_neighbors]
+    }
+  }
+}
+
+fn parse_counts(digits: &str) -> Result<Vec<usize>, String> {
+  digits.chars().map(|c| {
+    match c.to_digit(10) {
+      Some(d) if d <= 8 => Ok(d as usize),
+      _ => Err(format!("invalid neighbor count: {}", c))
+    }
+  }).collect()
+}
+
+// accepts "B3/S23" with the parts in either order and in either case
+impl FromStr for LifeRule {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<LifeRule, String> {
+    let mut birth = None;
+    let mut survival = None;
+
+    for part in s.trim().split('/') {
+      let mut chars = part.chars();
+
+      match chars.next() {
+        Some('B') | Some('b') if birth.is_none() => birth = Some(parse_counts(chars.as_str())?),
+        Some('S') | Some('s') if survival.is_none() => survival = Some(parse_counts(chars.as_str())?),
+        _ => return Err(format!("invalid rule: {}", s))
+      }
+    }
+
+    match (birth, survival) {
+      (Some(birth), Some(survival)) => LifeRule::new(&birth, &survival),
+      _ => Err(format!("invalid rule: {}", s))
+    }
+  }
+}
+
+impl fmt::Display for LifeRule {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "B")?;
+    for count in (0..9).filter(|&c| self.birth[c]) {
+      write!(f, "{}", count)?;
+    }
+
+    write!(f, "/S")?;
+    for count in (0..9).filter(|&c| self.survival[c]) {
+      write!(f, "{}", count)?;
+    }
+
+    Ok(())
+  }
+}
+
+pub fn life(grid: Flat2dArray<bool>, rule: LifeRule, bounds: Bounds) -> Automaton<bool, impl FnMut(&bool, &[bool]) -> bool> {
+  Automaton::new(grid, Neighborhood::Moore, bounds, move |&alive: &bool, neighbors: &[bool]| {
+    rule.next_state(alive, neighbors.iter().filter(|&&n| n).count())
+  })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+  pub cells: Flat2dArray<bool>,
+  pub rule: Option<LifeRule>
+}
+
+fn parse_header(line: &str) -> Result<(usize, usize, Option<LifeRule>), String> {
+  let mut width = None;
+  let mut height = None;
+  let mut rule = None;
+
+  for entry in line.split(',') {
+    let mut parts = entry.splitn(2, '=');
+    let key = parts.next().unwrap().trim();
+    let value = parts.next().ok_or(format!("invalid header entry: {}", entry.trim()))?.trim();
+
+    match key {
+      "x" => width = Some(value.parse::<usize>().map_err(|err| err.to_string())?),
+      "y" => height = Some(value.parse::<usize>().map_err(|err| err.to_string())?),
+      "rule" => rule = Some(value.parse::<LifeRule>()?),
+      _ => return Err(format!("unknown header entry: {}", key))
+    }
+  }
+
+  match (width, height) {
+    (Some(width), Some(height)) => Ok((width, height, rule)),
+    _ => Err("header needs x and y".to_string())
+  }
+}
+
+// the header is checked against this before anything is allocated
+const MAX_CELLS: usize = 1 << 30;
+
+pub fn read_rle(input: &str) -> Result<Pattern, String> {
+  let mut lines = input.lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+  let (width, height, rule) = parse_header(lines.next().ok_or("missing header")?)?;
+
+  if width.checked_mul(height).is_none_or(|cells| cells > MAX_CELLS) {
+    return Err(format!("pattern of {}x{} is too large", width, height));
+  }
+
+  let exceeds = || format!("pattern exceeds its size of {}x{}", width, height);
+
+  let mut cells = Flat2dArray::new(width, height, false);
+  let mut x: usize = 0;
+  let mut y: usize = 0;
+  let mut count: Option<usize> = None;
+
+  'body: for line in lines {
+    for c in line.chars() {
+      if let Some(d) = c.to_digit(10) {
+        count = Some(count.unwrap_or(0).checked_mul(10)
+          .and_then(|count| count.checked_add(d as usize))
+          .ok_or("run count too large")?);
+        continue;
+      }
+
+      let run = count.take().unwrap_or(1);
+
+      match c {
+        '!' => break 'body,
+        '$' => {
+          x = 0;
+          y = y.checked_add(run).ok_or_else(exceeds)?;
+        },
+        'b' | '.' => x = x.checked_add(run).ok_or_else(exceeds)?,
+        c if c.is_ascii_alphabetic() => {
+          if y >= height || x.checked_add(run).is_none_or(|end| end > width) {
+            return Err(exceeds());
+          }
+
+          for _ in 0..run {
+            cells[(x, y)] = true;
+            x += 1;
+          }
+        },
+        c if c.is_whitespace() => {},
+        c => return Err(format!("unexpected character: {}", c))
+      }
+    }
+  }
+
+  Ok(Pattern {
+    cells,
+    rule
+  })
+}
+
+fn push_run(runs: &mut Vec<String>, count: usize, tag: char) {
+  if count == 1 {
+    runs.push(tag.to_string());
+  } else if count > 1 {
+    runs.push(format!("{}{}", count, tag));
+  }
+}
+
+// lines are wrapped at 70 characters like most other tools do
+pub fn write_rle(cells: &Flat2dArray<bool>, rule: &LifeRule) -> String {
+  let mut runs = Vec::new();
+  let mut pending_rows = 0;
+
+  for row in cells.rows() {
+    let last = match row.iter().rposition(|&alive| alive) {
+      Some(last) => last + 1,
+      None => {
+        pending_rows += 1;
+        continue;
+      }
+    };
+
+    push_run(&mut runs, pending_rows, '$');
+    pending_rows = 1;
+
+    let mut x = 0;
+    while x < last {
+      let alive = row[x];
+      let length = row[x..last].iter().take_while(|&&c| c == alive).count();
+
+      push_run(&mut runs, length, if alive { 'o' } else { 'b' });
+      x += length;
+    }
+  }
+
+  runs.push("!".to_string());
+
+  let mut result = format!("x = {}, y = {}, rule = {}\n", cells.get_width(), cells.get_height(), rule);
+  let mut line_len = 0;
+
+  for run in runs {
+    if line_len + run.len() > 70 {
+      result.push('\n');
+      line_len = 0;
+    }
+
+    line_len += run.len();
+    result.push_str(&run);
+  }
+
+  result.push('\n');
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use dp256::*;
+  use dp256::automaton::*;
+  use dp256::life::*;
+  use dp256::neighborhood::*;
+
+  const GLIDER: &str = "#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!\n";
+
+  #[test]
+  fn test_rule() {
+    let rule: LifeRule = "B3/S23".parse().unwrap();
+
+    assert_eq!(LifeRule::conway(), rule);
+    assert_eq!(rule, "s23/b3".parse().unwrap());
+    assert_eq!("B36/S23", "B36/S23".parse::<LifeRule>().unwrap().to_string());
+    assert_eq!("B/S", "B/S".parse::<LifeRule>().unwrap().to_string());
+
+    assert!("B9/S23".parse::<LifeRule>().is_err());
+    assert!("B3".parse::<LifeRule>().is_err());
+    assert!("B3/B3".parse::<LifeRule>().is_err());
+  }
+
+  #[test]
+  fn test_blinker() {
+    let grid: Flat2dArray<bool> = Flat2dArray::from_fn(5, 5, |x, y| y == 2 && (1..4).contains(&x));
+    let mut automaton = life(grid.clone(), LifeRule::conway(), Bounds::Clip);
+
+    automaton.step();
+    assert_eq!(grid.transpose(), *automaton.get_grid());
+
+    assert_eq!(Some(Cycle { start: 1, period: 2 }), automaton.find_cycle(10));
+  }
+
+  #[test]
+  fn test_glider_on_torus() {
+    let pattern = read_rle(GLIDER).unwrap();
+    let mut grid = Flat2dArray::new(6, 6, false);
+
+    for ((x, y), &alive) in pattern.cells.iter_enumerated() {
+      grid[(x, y)] = alive;
+    }
+
+    let mut automaton = life(grid.clone(), pattern.rule.unwrap(), Bounds::Torus);
+
+    // a glider moves one cell diagonally every 4 generations and wraps
+    // around to where it started after 24
+    automaton.run(4);
+    assert_eq!(5, automaton.get_grid().iter().filter(|&&c| c).count());
+    assert!(automaton.get_grid()[(3, 3)]);
+
+    assert_eq!(Some(Cycle { start: 4, period: 24 }), automaton.find_cycle(100));
+
+    // on a bounded grid it ends up as a block in the corner
+    let mut automaton = life(grid, LifeRule::conway(), Bounds::Clip);
+    let cycle = automaton.find_cycle(100).unwrap();
+
+    assert_eq!(1, cycle.period);
+    assert_eq!(4, automaton.get_grid().iter().filter(|&&c| c).count());
+  }
+
+  #[test]
+  fn test_rle() {
+    let pattern = read_rle(GLIDER).unwrap();
+
+    assert_eq!(Flat2dArray::from(vec![
+      vec![false, true, false],
+      vec![false, false, true],
+      vec![true, true, true]
+    ]), pattern.cells);
+
+    assert_eq!("x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n", write_rle(&pattern.cells, &LifeRule::conway()));
+
+    let mut sparse = Flat2dArray::new(4, 5, false);
+    sparse[(3, 0)] = true;
+    sparse[(0, 3)] = true;
+
+    let rle = write_rle(&sparse, &LifeRule::conway());
+    assert_eq!("x = 4, y = 5, rule = B3/S23\n3bo3$o!\n", rle);
+    assert_eq!(sparse, read_rle(&rle).unwrap().cells);
+
+    let wide = Flat2dArray::from_fn(200, 1, |x, _| x % 3 == 0);
+    let rle = write_rle(&wide, &LifeRule::conway());
+
+    assert!(rle.lines().all(|line| line.len() <= 70));
+    assert_eq!(wide, read_rle(&rle).unwrap().cells);
+
+    assert!(read_rle("x = 2, y = 1\n3o!").is_err());
+    assert_eq!(None, read_rle("x = 1, y = 1\no!").unwrap().rule);
+  }
+
+  #[test]
+  fn test_rle_malformed() {
+    fn err(input: &str) -> String {
+      read_rle(input).err().unwrap()
+    }
+
+    assert_eq!("pattern of 4000000000x4000000000 is too large", err("x = 4000000000, y = 4000000000\no!"));
+    assert_eq!("pattern of 18446744073709551615x2 is too large", err("x = 18446744073709551615, y = 2\no!"));
+    assert_eq!("run count too large", err("x = 2, y = 2\n99999999999999999999999o!"));
+    assert_eq!("pattern exceeds its size of 2x2", err("x = 2, y = 2\n18446744073709551615bo!"));
+    assert_eq!("pattern exceeds its size of 2x2", err("x = 2, y = 2\no18446744073709551615$o!"));
+    assert_eq!("pattern exceeds its size of 2x2", err("x = 2, y = 2\n2$o!"));
+    // dead cells and row ends past the edge don't matter
+    assert!(read_rle("x = 2, y = 2\n2o3b$2o2$!").is_ok());
+    assert!(read_rle("bo$2bo!").is_err());
+  }
+
+}
@@ -0,0 +1,644 @@
+// Matrix arithmetic on numeric grids.
+//
+// The operators return a Result, adding or multiplying grids of mismatching
+// shapes is an error and doesn't panic. Floats are eliminated with partial
+// pivoting, integers with fraction-free (Bareiss) elimination so every
+// intermediate value stays exact.
+
+use std::ops::{
+  Add,
+  Div,
+  Mul,
+  Neg,
+  Sub
+};
+
+use super::Flat2dArray;
+
+pub trait Numeric: Copy + PartialEq + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> {
+  fn zero() -> Self;
+
+  fn one() -> Self;
+}
+
+pub trait Float: Numeric + PartialOrd + Div<Output = Self> + Neg<Output = Self> {
+  fn abs(self) -> Self;
+
+  fn epsilon() -> Self;
+}
+
+pub trait Integer: Numeric + Div<Output = Self> + Neg<Output = Self> {}
+
+macro_rules! impl_numeric {
+  ($zero:expr, $one:expr, $($t:ty),*) => {
+    $(
+      impl Numeric for $t {
+        fn zero() -> $t {
+          $zero
+        }
+
+        fn one() -> $t {
+          $one
+        }
+      }
+    )*
+  }
+}
+
+impl_numeric!(0, 1, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+impl_numeric!(0.0, 1.0, f32, f64);
+
+macro_rules! impl_float {
+  ($($t:ident),*) => {
+    $(
+      impl Float for $t {
+        fn abs(self) -> $t {
+          $t::abs(self)
+        }
+
+        fn epsilon() -> $t {
+          $t::EPSILON
+        }
+      }
+    )*
+  }
+}
+
+impl_float!(f32, f64);
+
+macro_rules! impl_integer {
+  ($($t:ty),*) => {
+    $(
+      impl Integer for $t {}
+    )*
+  }
+}
+
+impl_integer!(i8, i16, i32, i64, i128, isize);
+
+pub fn construct_zero_matrix<T: Numeric>(width: usize, height: usize) -> Flat2dArray<T> {
+  Flat2dArray::new(width, height, T::zero())
+}
+
+pub fn construct_identity_matrix<T: Numeric>(size: usize) -> Flat2dArray<T> {
+  Flat2dArray::from_fn(size, size, |x, y| if x == y { T::one() } else { T::zero() })
+}
+
+pub fn construct_diagonal_matrix<T: Numeric>(diagonal: &[T]) -> Flat2dArray<T> {
+  let size = diagonal.len();
+
+  Flat2dArray::from_fn(size, size, |x, y| if x == y { diagonal[x] } else { T::zero() })
+}
+
+fn check_same_shape<T>(a: &Flat2dArray<T>, b: &Flat2dArray<T>) -> Result<(), String> {
+  if a.get_width() != b.get_width() || a.get_height() != b.get_height() {
+    return Err(format!("shape mismatch: {}x{} and {}x{}", a.get_width(), a.get_height(), b.get_width(), b.get_height()));
+  }
+
+  Ok(())
+}
+
+fn check_square<T>(a: &Flat2dArray<T>) -> Result<usize, String> {
+  if a.get_width() != a.get_height() {
+    return Err(format!("expected a square matrix, got {}x{}", a.get_width(), a.get_height()));
+  }
+
+  Ok(a.get_width())
+}
+
+impl<T: Numeric> Flat2dArray<T> {
+  pub fn zip_with<F>(&self, other: &Flat2dArray<T>, mut f: F) -> Result<Flat2dArray<T>, String>
+  where F: FnMut(T, T) -> T {
+    check_same_shape(self, other)?;
+
+    let data: Vec<T> = self.iter().zip(other.iter()).map(|(&a, &b)| f(a, b)).collect();
+
+    Flat2dArray::from_vec(self.get_width(), self.get_height(), data)
+  }
+
+  // element-wise product, `*` is the matrix product
+  pub fn hadamard(&self, other: &Flat2dArray<T>) -> Result<Flat2dArray<T>, String> {
+    self.zip_with(other, |a, b| a * b)
+  }
+
+  pub fn scale(&self, factor: T) -> Flat2dArray<T> {
+    self.map(|&value| value * factor)
+  }
+
+  pub fn mat_mul(&self, other: &Flat2dArray<T>) -> Result<Flat2dArray<T>, String> {
+    if self.get_width() != other.get_height() {
+      return Err(format!("can't multiply {}x{} with {}x{}", self.get_width(), self.get_height(), other.get_width(), other.get_height()));
+    }
+
+    Ok(Flat2dArray::from_fn(other.get_width(), self.get_height(), |x, y| {
+      (0..self.get_width()).fold(T::zero(), |sum, i| sum + self[(i, y)] * other[(x, i)])
+    }))
+  }
+
+  pub fn trace(&self) -> Result<T, String> {
+    let size = check_square(self)?;
+
+    Ok((0..size).fold(T::zero(), |sum, i| sum + self[(i, i)]))
+  }
+}
+
+impl<'a, T: Numeric> Add for &'a Flat2dArray<T> {
+  type Output = Result<Flat2dArray<T>, String>;
+
+  fn add(self, other: &'a Flat2dArray<T>) -> Result<Flat2dArray<T>, String> {
+    self.zip_with(other, |a, b| a + b)
+  }
+}
+
+impl<'a, T: Numeric> Sub for &'a Flat2dArray<T> {
+  type Output = Result<Flat2dArray<T>, String>;
+
+  fn sub(self, other: &'a Flat2dArray<T>) -> Result<Flat2dArray<T>, String> {
+    self.zip_with(other, |a, b| a - b)
+  }
+}
+
+impl<'a, T: Numeric> Mul for &'a Flat2dArray<T> {
+  type Output = Result<Flat2dArray<T>, String>;
+
+  fn mul(self, other: &'a Flat2dArray<T>) -> Result<Flat2dArray<T>, String> {
+    self.mat_mul(other)
+  }
+}
+
+impl<T: Numeric + Neg<Output = T>> Neg for &Flat2dArray<T> {
+  type Output = Flat2dArray<T>;
+
+  fn neg(self) -> Flat2dArray<T> {
+    self.map(|&value| -value)
+  }
+}
+
+impl<T: Numeric> Add for Flat2dArray<T> {
+  type Output = Result<Flat2dArray<T>, String>;
+
+  fn add(self, other: Flat2dArray<T>) -> Result<Flat2dArray<T>, String> {
+    &self + &other
+  }
+}
+
+impl<T: Numeric> Sub for Flat2dArray<T> {
+  type Output = Result<Flat2dArray<T>, String>;
+
+  fn sub(self, other: Flat2dArray<T>) -> Result<Flat2dArray<T>, String> {
+    &self - &other
+  }
+}
+
+impl<T: Numeric> Mul for Flat2dArray<T> {
+  type Output = Result<Flat2dArray<T>, String>;
+
+  fn mul(self, other: Flat2dArray<T>) -> Result<Flat2dArray<T>, String> {
+    &self * &other
+  }
+}
+
+impl<T: Numeric + Neg<Output = T>> Neg for Flat2dArray<T> {
+  type Output = Flat2dArray<T>;
+
+  fn neg(self) -> Flat2dArray<T> {
+    -&self
+  }
+}
+
+fn swap_rows<T>(m: &mut Flat2dArray<T>, a: usize, b: usize) {
+  if a == b {
+    return;
+  }
+
+  let width = m.get_width();
+  let data = m.as_mut_slice();
+
+  for x in 0..width {
+    data.swap(a * width + x, b * width + x);
+  }
+}
+
+// row echelon form with partial pivoting, returns the sign of the row
+// permutation and the pivot columns
+fn float_echelon<T: Float>(m: &mut Flat2dArray<T>, tolerance: T) -> (T, Vec<usize>) {
+  let width = m.get_width();
+  let height = m.get_height();
+
+  let mut sign = T::one();
+  let mut pivots = Vec::new();
+  let mut row = 0;
+
+  for col in 0..width {
+    if row >= height {
+      break;
+    }
+
+    let mut best = row;
+    for y in row + 1 .. height {
+      if m[(col, y)].abs() > m[(col, best)].abs() {
+        best = y;
+      }
+    }
+
+    if m[(col, best)].abs() <= tolerance {
+      continue;
+    }
+
+    if best != row {
+      swap_rows(m, best, row);
+      sign = -sign;
+    }
+
+    for y in row + 1 .. height {
+      let factor = m[(col, y)] / m[(col, row)];
+
+      for x in col..width {
+        let value = m[(x, y)] - factor * m[(x, row)];
+        m[(x, y)] = value;
+      }
+    }
+
+    pivots.push(col);
+    row += 1;
+  }
+
+  (sign, pivots)
+}
+
+// largest magnitude times epsilon, scaled by the size of the matrix
+fn default_tolerance<T: Float>(m: &Flat2dArray<T>) -> T {
+  let largest = m.iter().fold(T::zero(), |max, &value| if value.abs() > max { value.abs() } else { max });
+  let size = std::cmp::max(m.get_width(), m.get_height());
+
+  (0..size).fold(largest * T::epsilon(), |tolerance, _| tolerance + largest * T::epsilon())
+}
+
+fn float_determinant<T: Float>(m: &Flat2dArray<T>) -> Result<T, String> {
+  let size = check_square(m)?;
+  let mut work = m.clone();
+
+  let (sign, pivots) = float_echelon(&mut work, T::zero());
+
+  if pivots.len() < size {
+    return Ok(T::zero());
+  }
+
+  Ok((0..size).fold(sign, |det, i| det * work[(i, i)]))
+}
+
+fn float_rank<T: Float>(m: &Flat2dArray<T>, tolerance: T) -> usize {
+  let mut work = m.clone();
+
+  float_echelon(&mut work, tolerance).1.len()
+}
+
+fn float_inverse<T: Float>(m: &Flat2dArray<T>) -> Result<Flat2dArray<T>, String> {
+  let size = check_square(m)?;
+
+  if size == 0 {
+    return Ok(Flat2dArray::new_zero_sized());
+  }
+
+  let tolerance = default_tolerance(m);
+
+  // Gauss-Jordan on [m | identity]
+  let mut work = Flat2dArray::from_fn(2 * size, size, |x, y| {
+    if x < size { m[(x, y)] } else if x - size == y { T::one() } else { T::zero() }
+  });
+
+  let (_, pivots) = float_echelon(&mut work, tolerance);
+
+  if pivots.len() < size || pivots[size - 1] >= size {
+    return Err("matrix is singular".to_string());
+  }
+
+  for row in (0..size).rev() {
+    let pivot = work[(row, row)];
+
+    for x in 0 .. 2 * size {
+      let value = work[(x, row)] / pivot;
+      work[(x, row)] = value;
+    }
+
+    for y in 0..row {
+      let factor = work[(row, y)];
+
+      for x in 0 .. 2 * size {
+        let value = work[(x, y)] - factor * work[(x, row)];
+        work[(x, y)] = value;
+      }
+    }
+  }
+
+  Ok(Flat2dArray::from_fn(size, size, |x, y| work[(x + size, y)]))
+}
+
+// fraction-free echelon form, every division is exact; returns the sign of
+// the row permutation and the pivot columns
+fn bareiss_echelon<T: Integer>(m: &mut Flat2dArray<T>) -> (T, Vec<usize>) {
+  let width = m.get_width();
+  let height = m.get_height();
+
+  let mut sign = T::one();
+  let mut previous = T::one();
+  let mut pivots = Vec::new();
+  let mut row = 0;
+
+  for col in 0..width {
+    if row >= height {
+      break;
+    }
+
+    let pivot_row = match (row..height).find(|&y| m[(col, y)] != T::zero()) {
+      Some(y) => y,
+      None => continue
+    };
+
+    if pivot_row != row {
+      swap_rows(m, pivot_row, row);
+      sign = -sign;
+    }
+
+    let pivot = m[(col, row)];
+
+    for y in row + 1 .. height {
+      let factor = m[(col, y)];
+
+      for x in col + 1 .. width {
+        let value = (pivot * m[(x, y)] - factor * m[(x, row)]) / previous;
+        m[(x, y)] = value;
+      }
+
+      m[(col, y)] = T::zero();
+    }
+
+    previous = pivot;
+    pivots.push(col);
+    row += 1;
+  }
+
+  (sign, pivots)
+}
+
+fn integer_determinant<T: Integer>(m: &Flat2dArray<T>) -> Result<T, String> {
+  let size = check_square(m)?;
+
+  if size == 0 {
+    return Ok(T::one());
+  }
+
+  let mut work = m.clone();
+  let (sign, pivots) = bareiss_echelon(&mut work);
+
+  if pivots.len() < size {
+    return Ok(T::zero());
+  }
+
+  // the last pivot of the fraction-free form is the determinant itself
+  Ok(sign * work[(size - 1, size - 1)])
+}
+
+fn integer_rank<T: Integer>(m: &Flat2dArray<T>) -> usize {
+  let mut work = m.clone();
+
+  bareiss_echelon(&mut work).1.len()
+}
+
+// returns the adjugate and the determinant, the inverse is adjugate / determinant
+fn integer_inverse<T: Integer>(m: &Flat2dArray<T>) -> Result<(Flat2dArray<T>, T), String> {
+  let size = check_square(m)?;
+  let det = integer_determinant(m)?;
+
+  if det == T::zero() {
+    return Err("matrix is singular".to_string());
+  }
+
+  // fraction-free Gauss-Jordan on [m | identity], afterwards the left half is
+  // the last pivot times the identity
+  let mut work = Flat2dArray::from_fn(2 * size, size, |x, y| {
+    if x < size { m[(x, y)] } else if x - size == y { T::one() } else { T::zero() }
+  });
+
+  let mut previous = T::one();
+
+  for k in 0..size {
+    let pivot_row = (k..size).find(|&y| work[(k, y)] != T::zero()).unwrap();
+    swap_rows(&mut work, pivot_row, k);
+
+    let pivot = work[(k, k)];
+
+    for y in 0..size {
+      if y == k {
+        continue;
+      }
+
+      let factor = work[(k, y)];
+
+      for x in 0 .. 2 * size {
+        if x == k {
+          continue;
+        }
+
+        let value = (pivot * work[(x, y)] - factor * work[(x, k)]) / previous;
+        work[(x, y)] = value;
+      }
+
+      work[(k, y)] = T::zero();
+    }
+
+    previous = pivot;
+  }
+
+  // the left half is now the last pivot times the identity, so the right half
+  // is the last pivot times the inverse. The last pivot is the determinant up
+  // to the sign of the row swaps, which makes the right half ± the adjugate
+  let last = previous;
+  let adjugate = Flat2dArray::from_fn(size, size, |x, y| work[(x + size, y)]);
+
+  if last == det {
+    Ok((adjugate, det))
+  } else {
+    Ok((-adjugate, det))
+  }
+}
+
+macro_rules! impl_float_matrix {
+  ($($t:ty),*) => {
+    $(
+      impl Flat2dArray<$t> {
+        pub fn determinant(&self) -> Result<$t, String> {
+          float_determinant(self)
+        }
+
+        pub fn inverse(&self) -> Result<Flat2dArray<$t>, String> {
+          float_inverse(self)
+        }
+
+        // singular values below the tolerance count as zero
+        pub fn rank_with_tolerance(&self, tolerance: $t) -> usize {
+          float_rank(self, tolerance)
+        }
+
+        pub fn rank(&self) -> usize {
+          float_rank(self, default_tolerance(self))
+        }
+      }
+    )*
+  }
+}
+
+impl_float_matrix!(f32, f64);
+
+macro_rules! impl_integer_matrix {
+  ($($t:ty),*) => {
+    $(
+      impl Flat2dArray<$t> {
+        pub fn determinant(&self) -> Result<$t, String> {
+          integer_determinant(self)
+        }
+
+        pub fn inverse_fraction_free(&self) -> Result<(Flat2dArray<$t>, $t), String> {
+          integer_inverse(self)
+        }
+
+        pub fn rank(&self) -> usize {
+          integer_rank(self)
+        }
+      }
+    )*
+  }
+}
+
+impl_integer_matrix!(i8, i16, i32, i64, i128, isize);
+
+#[cfg(test)]
+mod tests {
+  use dp256::*;
+  use dp256::matrix::*;
+
+  fn approx_eq(a: &Flat2dArray<f64>, b: &Flat2dArray<f64>) -> bool {
+    a.get_width() == b.get_width() && a.get_height() == b.get_height() &&
+      a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-9)
+  }
+
+  #[test]
+  fn test_element_wise() {
+    let a = Flat2dArray::from(vec![vec![1, 2], vec![3, 4]]);
+    let b = Flat2dArray::from(vec![vec![10, 20], vec![30, 40]]);
+
+    assert_eq!(Flat2dArray::from(vec![vec![11, 22], vec![33, 44]]), (&a + &b).unwrap());
+    assert_eq!(Flat2dArray::from(vec![vec![9, 18], vec![27, 36]]), (&b - &a).unwrap());
+    assert_eq!(Flat2dArray::from(vec![vec![10, 40], vec![90, 160]]), a.hadamard(&b).unwrap());
+    assert_eq!(Flat2dArray::from(vec![vec![-1, -2], vec![-3, -4]]), -&a);
+    assert_eq!(Flat2dArray::from(vec![vec![2, 4], vec![6, 8]]), a.scale(2));
+
+    let c: Flat2dArray<i32> = construct_zero_matrix(3, 2);
+    assert!((&a + &c).is_err());
+    assert!(a.hadamard(&c).is_err());
+  }
+
+  #[test]
+  fn test_mat_mul() {
+    let a = construct_regular_matrix(3, 2);
+    let b = construct_regular_matrix(2, 3);
+
+    // 0 1 2     0 1
+    // 3 4 5  *  2 3
+    //           4 5
+    assert_eq!(Flat2dArray::from(vec![vec![10, 13], vec![28, 40]]), (&a * &b).unwrap());
+    assert_eq!(a, (&a * &construct_identity_matrix(3)).unwrap());
+    assert!((&a * &a).is_err());
+    assert!((a.clone() * a).is_err());
+  }
+
+  #[test]
+  fn test_constructors() {
+    let identity: Flat2dArray<i32> = construct_identity_matrix(3);
+
+    assert_eq!(Ok(3), identity.trace());
+    assert_eq!(construct_diagonal_matrix(&[1, 1, 1]), identity);
+    assert!(construct_zero_matrix::<f64>(2, 3).iter().all(|&v| v == 0.0));
+  }
+
+  #[test]
+  fn test_float_determinant() {
+    let m = Flat2dArray::from(vec![vec![2.0f64, -3.0, 1.0], vec![2.0, 0.0, -1.0], vec![1.0, 4.0, 5.0]]);
+
+    assert!((m.determinant().unwrap() - 49.0).abs() < 1e-9);
+
+    let singular = Flat2dArray::from(vec![vec![1.0f64, 2.0], vec![2.0, 4.0]]);
+    assert_eq!(Ok(0.0), singular.determinant());
+
+    assert!(Flat2dArray::from(vec![vec![1.0f64, 2.0]]).determinant().is_err());
+  }
+
+  #[test]
+  fn test_float_inverse() {
+    let m = Flat2dArray::from(vec![vec![0.0f64, 2.0, 1.0], vec![1.0, 1.0, 0.0], vec![3.0, 0.0, 1.0]]);
+    let inverse = m.inverse().unwrap();
+
+    assert!(approx_eq(&construct_identity_matrix(3), &(&m * &inverse).unwrap()));
+    assert!(approx_eq(&construct_identity_matrix(3), &(&inverse * &m).unwrap()));
+
+    assert!(Flat2dArray::from(vec![vec![1.0f64, 2.0], vec![2.0, 4.0]]).inverse().is_err());
+    assert_eq!(Ok(Flat2dArray::new_zero_sized()), Flat2dArray::<f64>::new_zero_sized().inverse());
+  }
+
+  #[test]
+  fn test_float_rank() {
+    let m = Flat2dArray::from(vec![vec![1.0f64, 2.0, 3.0], vec![2.0, 4.0, 6.0], vec![1.0, 0.0, 1.0]]);
+
+    assert_eq!(2, m.rank());
+    assert_eq!(2, m.transpose().rank());
+    assert_eq!(0, construct_zero_matrix::<f64>(3, 2).rank());
+    assert_eq!(1, Flat2dArray::from(vec![vec![1.0f64, 1e-12]]).rank_with_tolerance(1e-6));
+  }
+
+  #[test]
+  fn test_integer_determinant() {
+    let m = Flat2dArray::from(vec![vec![2i64, -3, 1], vec![2, 0, -1], vec![1, 4, 5]]);
+    assert_eq!(Ok(49), m.determinant());
+
+    let needs_pivot = Flat2dArray::from(vec![vec![0i64, 1], vec![1, 0]]);
+    assert_eq!(Ok(-1), needs_pivot.determinant());
+
+    let singular = Flat2dArray::from(vec![vec![1i32, 2, 3], vec![4, 5, 6], vec![7, 8, 9]]);
+    assert_eq!(Ok(0), singular.determinant());
+    assert_eq!(2, singular.rank());
+  }
+
+  #[test]
+  fn test_integer_inverse_large() {
+    // the adjugate times the determinant would be 2^63
+    let m = Flat2dArray::from(vec![vec![2i64, 0], vec![0, 1 << 31]]);
+    let (adjugate, det) = m.inverse_fraction_free().unwrap();
+
+    assert_eq!(1 << 32, det);
+    assert_eq!(Flat2dArray::from(vec![vec![1i64 << 31, 0], vec![0, 2]]), adjugate);
+
+    let swapped = Flat2dArray::from(vec![vec![0i64, 1 << 31], vec![2, 0]]);
+    assert_eq!(Ok((Flat2dArray::from(vec![vec![0, -(1i64 << 31)], vec![-2, 0]]), -(1 << 32))), swapped.inverse_fraction_free());
+  }
+
+  #[test]
+  fn test_integer_matches_float() {
+    for seed in 1..40i64 {
+      let m = Flat2dArray::from_fn(4, 4, |x, y| ((x as i64 * 7 + y as i64 * 13 + seed * seed) % 11) - 5);
+      let f = m.map(|&v| v as f64);
+
+      let det = m.determinant().unwrap();
+      assert!((det as f64 - f.determinant().unwrap()).abs() < 1e-6);
+      assert_eq!(m.rank(), f.rank());
+
+      if det != 0 {
+        let (adjugate, d) = m.inverse_fraction_free().unwrap();
+        assert_eq!(det, d);
+        assert_eq!(construct_identity_matrix::<i64>(4).scale(det), (&m * &adjugate).unwrap());
+      } else {
+        assert!(m.inverse_fraction_free().is_err());
+      }
+    }
+  }
+
+}
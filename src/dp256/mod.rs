@@ -0,0 +1,673 @@
+pub mod automaton;
+pub mod image;
+pub mod life;
+pub mod matrix;
+pub mod nd;
+pub mod neighborhood;
+pub mod search;
+pub mod storage;
+pub mod text;
+pub mod traversal;
+pub mod view;
+
+use std::ops::{
+  Index,
+  IndexMut
+};
+
+pub use self::nd::FlatNdArray;
+
+// read access shared by arrays and their views
+pub trait Grid {
+  type Item;
+
+  fn get_width(&self) -> usize;
+
+  fn get_height(&self) -> usize;
+
+  fn get_ref(&self, x: usize, y: usize) -> Option<&Self::Item>;
+
+  fn is_out_of_bounds(&self, x: usize, y: usize) -> bool {
+    x >= self.get_width() || y >= self.get_height()
+  }
+
+  fn traverse<'a, I>(&'a self, coords: I) -> impl Iterator<Item = &'a Self::Item>
+  where I: IntoIterator<Item = (usize, usize)> + 'a {
+    coords.into_iter().map(move |(x, y)| {
+      match self.get_ref(x, y) {
+        Some(value) => value,
+        None => panic!("Out of bounds: {}, {}", x, y)
+      }
+    })
+  }
+}
+
+pub trait GridMut: Grid {
+  fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut Self::Item>;
+
+  fn set(&mut self, x: usize, y: usize, val: Self::Item) {
+    match self.get_mut(x, y) {
+      Some(value) => *value = val,
+      None => panic!("Out of bounds: {}, {}", x, y)
+    }
+  }
+
+  // inverse of `traverse`, the values have to match the traversal in length.
+  // Nothing is written unless the whole traversal fits
+  fn write_traversal<I, V>(&mut self, coords: I, values: V) -> Result<(), String>
+  where I: IntoIterator<Item = (usize, usize)>, V: IntoIterator<Item = Self::Item> {
+    let mut values = values.into_iter();
+    let mut pending = Vec::new();
+
+    for (x, y) in coords {
+      if self.is_out_of_bounds(x, y) {
+        return Err(format!("Out of bounds: {}, {}", x, y));
+      }
+
+      let value = values.next().ok_or(format!("traversal needs more than {} values", pending.len()))?;
+
+      pending.push((x, y, value));
+    }
+
+    if values.next().is_some() {
+      return Err(format!("traversal takes only {} values", pending.len()));
+    }
+
+    for (x, y, value) in pending {
+      self.set(x, y, value);
+    }
+
+    Ok(())
+  }
+}
+
+// indexed by (x, y), the underlying shape is [height, width]
+pub type Flat2dArray<T> = FlatNdArray<T, 2>;
+
+impl<T: Default + Clone> Flat2dArray<T> {
+  pub fn new_default(width: usize, height: usize) -> Flat2dArray<T> {
+    Flat2dArray::new(width, height, T::default())
+  }
+}
+
+impl<T: Clone> Flat2dArray<T> {
+  pub fn new(width: usize, height: usize, value: T) -> Flat2dArray<T> {
+    FlatNdArray::from_elem([height, width], value)
+  }
+
+  pub fn transpose(&self) -> Flat2dArray<T> {
+    Flat2dArray::from_fn(self.get_height(), self.get_width(), |x, y| self[(y, x)].clone())
+  }
+
+  // all rotations are clockwise
+  pub fn rotate_90(&self) -> Flat2dArray<T> {
+    Flat2dArray::from_fn(self.get_height(), self.get_width(), |x, y| self[(y, self.get_height() - 1 - x)].clone())
+  }
+
+  pub fn rotate_180(&self) -> Flat2dArray<T> {
+    Flat2dArray::from_fn(self.get_width(), self.get_height(), |x, y| self[(self.get_width() - 1 - x, self.get_height() - 1 - y)].clone())
+  }
+
+  pub fn rotate_270(&self) -> Flat2dArray<T> {
+    Flat2dArray::from_fn(self.get_height(), self.get_width(), |x, y| self[(self.get_width() - 1 - y, x)].clone())
+  }
+
+  // mirrors left and right
+  pub fn flip_horizontal(&self) -> Flat2dArray<T> {
+    Flat2dArray::from_fn(self.get_width(), self.get_height(), |x, y| self[(self.get_width() - 1 - x, y)].clone())
+  }
+
+  // mirrors top and bottom
+  pub fn flip_vertical(&self) -> Flat2dArray<T> {
+    Flat2dArray::from_fn(self.get_width(), self.get_height(), |x, y| self[(x, self.get_height() - 1 - y)].clone())
+  }
+}
+
+impl<T: Copy> Flat2dArray<T> {
+  pub fn get(&self, x: usize, y: usize) -> T {
+    self[(x, y)]
+  }
+}
+
+impl<T> Flat2dArray<T> {
+  pub fn new_zero_sized() -> Flat2dArray<T> {
+    FlatNdArray::from_shape_fn([0, 0], |_| unreachable!())
+  }
+
+  pub fn from_fn<F>(width: usize, height: usize, mut f: F) -> Flat2dArray<T>
+  where F: FnMut(usize, usize) -> T {
+    FlatNdArray::from_shape_fn([height, width], |[y, x]| f(x, y))
+  }
+
+  pub fn from_vec(width: usize, height: usize, data: Vec<T>) -> Result<Flat2dArray<T>, String> {
+    if data.len() != width * height {
+      return Err(format!("expected {} elements for a {}x{} array, got {}", width * height, width, height, data.len()));
+    }
+
+    FlatNdArray::from_shape_vec([height, width], data)
+  }
+
+  // all rows have to be as long as the first
+  pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Flat2dArray<T>, String> {
+    let width = rows.first().map_or(0, |row| row.len());
+    let height = rows.len();
+
+    if let Some(y) = rows.iter().position(|row| row.len() != width) {
+      return Err(format!("row {} has {} cells, expected {}", y + 1, rows[y].len(), width));
+    }
+
+    if width == 0 {
+      return Ok(Flat2dArray::new_zero_sized());
+    }
+
+    Flat2dArray::from_vec(width, height, rows.into_iter().flatten().collect())
+  }
+
+  pub fn is_out_of_bounds(&self, x: usize, y: usize) -> bool {
+    x >= self.get_width() || y >= self.get_height()
+  }
+
+  fn assert_not_out_of_bounds(&self, x: usize, y: usize) {
+    if self.is_out_of_bounds(x, y) {
+      panic!("Out of bounds: {}, {}", x, y);
+    }
+  }
+
+  fn get_index(&self, x: usize, y: usize) -> usize {
+    self.assert_not_out_of_bounds(x, y);
+    x + y * self.get_width()
+  }
+
+  pub fn get_ref(&self, x: usize, y: usize) -> Option<&T> {
+    if self.is_out_of_bounds(x, y) {
+      return None;
+    }
+
+    self.get_at([y, x])
+  }
+
+  pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+    if self.is_out_of_bounds(x, y) {
+      return None;
+    }
+
+    self.get_at_mut([y, x])
+  }
+
+  pub fn set(&mut self, x: usize, y: usize, val: T) {
+    let index = self.get_index(x, y);
+
+    self.as_mut_slice()[index] = val;
+  }
+
+  pub fn get_width(&self) -> usize {
+    self.shape()[1]
+  }
+
+  pub fn get_height(&self) -> usize {
+    self.shape()[0]
+  }
+
+  fn assert_row_exists(&self, y: usize) {
+    if y >= self.get_height() {
+      panic!("Out of bounds: row {}", y);
+    }
+  }
+
+  fn assert_column_exists(&self, x: usize) {
+    if x >= self.get_width() {
+      panic!("Out of bounds: column {}", x);
+    }
+  }
+
+  pub fn row(&self, y: usize) -> &[T] {
+    self.assert_row_exists(y);
+    let width = self.get_width();
+    &self.as_slice()[y * width .. (y + 1) * width]
+  }
+
+  pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+    self.assert_row_exists(y);
+    let width = self.get_width();
+    &mut self.as_mut_slice()[y * width .. (y + 1) * width]
+  }
+
+  pub fn rows(&self) -> Rows<'_, T> {
+    Rows {
+      array: self,
+      y: 0
+    }
+  }
+
+  pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+    self.assert_column_exists(x);
+    self.iter().skip(x).step_by(self.get_width())
+  }
+
+  pub fn column_mut(&mut self, x: usize) -> impl Iterator<Item = &mut T> {
+    self.assert_column_exists(x);
+    let width = self.get_width();
+    self.iter_mut().skip(x).step_by(width)
+  }
+
+  pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+    (0..self.get_width()).map(move |x| self.iter().skip(x).step_by(self.get_width()))
+  }
+
+  pub fn iter_enumerated(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+    let width = self.get_width();
+    self.iter().enumerate().map(move |(i, value)| ((i % width, i / width), value))
+  }
+}
+
+impl<T> Grid for Flat2dArray<T> {
+  type Item = T;
+
+  fn get_width(&self) -> usize {
+    Flat2dArray::get_width(self)
+  }
+
+  fn get_height(&self) -> usize {
+    Flat2dArray::get_height(self)
+  }
+
+  fn get_ref(&self, x: usize, y: usize) -> Option<&T> {
+    Flat2dArray::get_ref(self, x, y)
+  }
+}
+
+impl<T> GridMut for Flat2dArray<T> {
+  fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+    Flat2dArray::get_mut(self, x, y)
+  }
+}
+
+pub struct Rows<'a, T: 'a> {
+  array: &'a Flat2dArray<T>,
+  y: usize
+}
+
+impl<'a, T> Iterator for Rows<'a, T> {
+  type Item = &'a [T];
+
+  fn next(&mut self) -> Option<&'a [T]> {
+    if self.y >= self.array.get_height() {
+      return None;
+    }
+
+    let row = self.array.row(self.y);
+    self.y += 1;
+
+    Some(row)
+  }
+}
+
+impl<T> Index<(usize, usize)> for Flat2dArray<T> {
+  type Output = T;
+
+  fn index(&self, (x, y): (usize, usize)) -> &T {
+    let index = self.get_index(x, y);
+
+    &self.as_slice()[index]
+  }
+}
+
+impl<T> IndexMut<(usize, usize)> for Flat2dArray<T> {
+  fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+    let index = self.get_index(x, y);
+
+    &mut self.as_mut_slice()[index]
+  }
+}
+
+// panics on ragged rows, `from_rows` reports them
+impl<T> From<Vec<Vec<T>>> for Flat2dArray<T> {
+  fn from(vec: Vec<Vec<T>>) -> Flat2dArray<T> {
+    match Flat2dArray::from_rows(vec) {
+      Ok(array) => array,
+      Err(message) => panic!("{}", message)
+    }
+  }
+}
+
+pub fn construct_regular_matrix(width: usize, height: usize) -> Flat2dArray<usize> {
+  let mut array: Flat2dArray<usize> = Flat2dArray::new_default(width, height);
+
+  for y in 0..height {
+    for x in 0..width {
+      array.set(x, y, x + width * y);
+    }
+  }
+
+  array
+}
+
+pub fn oblique<G>(grid: &G) -> Vec<Vec<G::Item>>
+where G: Grid, G::Item: Clone {
+  traversal::anti_diagonals(grid.get_width(), grid.get_height())
+    .map(|line| grid.traverse(line).cloned().collect())
+    .collect()
+}
+
+fn oblique_part_len(width: usize, height: usize, part: usize) -> usize {
+  let start_x = if part < width { part } else { width - 1 };
+  let start_y = part - start_x;
+
+  // walks down-left until either the left or the bottom border is hit
+  std::cmp::min(start_x + 1, height - start_y)
+}
+
+// an array and its transpose produce diagonals of the same lengths,
+// so the intended shape can't be derived from the diagonals alone
+pub fn de_oblique<T>(vec: &[Vec<T>], width: usize, height: usize) -> Result<Flat2dArray<T>, String>
+where T: Clone {
+  if width == 0 || height == 0 {
+    if !vec.is_empty() {
+      return Err(format!("expected no diagonals for a {}x{} array, got {}", width, height, vec.len()));
+    }
+
+    return Ok(Flat2dArray::new_zero_sized());
+  }
+
+  let part_count = width + height - 1;
+
+  if vec.len() != part_count {
+    return Err(format!("expected {} diagonals for a {}x{} array, got {}", part_count, width, height, vec.len()));
+  }
+
+  for (part, part_vec) in vec.iter().enumerate() {
+    let expected_len = oblique_part_len(width, height, part);
+
+    if part_vec.len() != expected_len {
+      return Err(format!("expected diagonal {} to have length {}, got {}", part, expected_len, part_vec.len()));
+    }
+  }
+
+  Ok(Flat2dArray::from_fn(width, height, |x, y| {
+    let part = x + y;
+    let start_x = if part < width { part } else { width - 1 };
+
+    vec[part][start_x - x].clone()
+  }))
+}
+
+pub fn de_oblique_square<T>(vec: &[Vec<T>]) -> Result<Flat2dArray<T>, String>
+where T: Clone {
+  if vec.len().is_multiple_of(2) && !vec.is_empty() {
+    return Err(format!("a square array has an odd number of diagonals, got {}", vec.len()));
+  }
+
+  let size = vec.len().div_ceil(2);
+
+  de_oblique(vec, size, size)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fmt;
+  use dp256::*;
+
+  fn print_obliqued_vec<T: fmt::Display>(vv: &Vec<Vec<T>>) {
+    println!("");
+    for v in vv {
+      for value in v {
+        print!("{: >3}", value);
+      }
+      println!("");
+    }
+  }
+
+  #[test]
+  fn test_create() {
+    let a2d: Flat2dArray<i32> = Flat2dArray::new_default( 5, 10 );
+
+    assert_eq!( 5, a2d.get_width() );
+    assert_eq!( 10, a2d.get_height() );
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_out_of_bounds() {
+    let a2d: Flat2dArray<i32> = Flat2dArray::new_default(5, 10);
+
+    a2d.get(6, 5);
+  }
+
+  #[test]
+  fn test_regular_matrix() {
+    let a2d = construct_regular_matrix(10, 5);
+
+    for x in 0 .. a2d.get_width() {
+      for y in 0 .. a2d.get_height() {
+        assert_eq!( x + y * a2d.get_width(), a2d.get(x, y) );
+      }
+    }
+
+    println!("{}", a2d);
+  }
+
+  #[test]
+  fn test_oblique() {
+    let array = construct_regular_matrix(6, 6);
+
+    let vec = oblique(&array);
+
+    print_obliqued_vec(&vec);
+  }
+
+  #[test]
+  fn test_from_vec() {
+    let mut vec: Vec<Vec<usize>> = Vec::new();
+    for y in 0..6 {
+      let mut part_vec: Vec<usize> = Vec::new();
+      for x in 0..7 {
+        part_vec.push(x * y);
+      }
+      vec.push(part_vec);
+    }
+
+    let vec_copy: Vec<Vec<usize>> = vec.to_vec();
+
+    let array = Flat2dArray::from(vec);
+
+    assert_eq!(6, array.get_height());
+    assert_eq!(7, array.get_width());
+
+    for y in 0..6 {
+      for x in 0..7 {
+        assert_eq!(vec_copy[y][x], array.get(x, y));
+      }
+    }
+  }
+
+  #[test]
+  fn test_de_oblique_square() {
+    let array = construct_regular_matrix(6, 6);
+
+    let vec = oblique(&array);
+    let result = de_oblique_square(&vec).unwrap();
+
+    assert_eq!(6, result.get_width());
+    assert_eq!(6, result.get_height());
+
+    for y in 0..6 {
+      for x in 0..6 {
+        assert_eq!(array.get(x, y), result.get(x, y));
+      }
+    }
+  }
+
+  #[test]
+  fn test_de_oblique_round_trip() {
+    for width in 1..12 {
+      for height in 1..12 {
+        let array = construct_regular_matrix(width, height);
+
+        let vec = oblique(&array);
+        let result = de_oblique(&vec, width, height).unwrap();
+
+        assert_eq!(width, result.get_width());
+        assert_eq!(height, result.get_height());
+
+        for y in 0..height {
+          for x in 0..width {
+            assert_eq!(array.get(x, y), result.get(x, y));
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn test_de_oblique_empty() {
+    let vec: Vec<Vec<usize>> = Vec::new();
+
+    let result = de_oblique_square(&vec).unwrap();
+
+    assert_eq!(0, result.get_width());
+    assert_eq!(0, result.get_height());
+    assert!(de_oblique(&[vec![1]], 0, 3).is_err());
+  }
+
+  #[test]
+  fn test_de_oblique_invalid() {
+    let vec = oblique(&construct_regular_matrix(4, 3));
+
+    assert!(de_oblique(&vec, 5, 2).is_err());
+    assert!(de_oblique(&vec, 4, 4).is_err());
+    assert!(de_oblique(&vec[1..], 4, 3).is_err());
+    assert!(de_oblique_square(&vec).is_err());
+
+    let mut too_long = vec.to_vec();
+    too_long[2].push(0);
+    assert!(de_oblique(&too_long, 4, 3).is_err());
+
+    let mut too_short = vec.to_vec();
+    too_short[3].pop();
+    assert!(de_oblique(&too_short, 4, 3).is_err());
+  }
+
+  #[test]
+  fn test_index() {
+    let mut array = construct_regular_matrix(4, 3);
+
+    assert_eq!(6, array[(2, 1)]);
+
+    array[(2, 1)] = 100;
+
+    assert_eq!(100, array.get(2, 1));
+    assert_eq!(Some(&100), array.get_ref(2, 1));
+    assert_eq!(None, array.get_ref(4, 1));
+    assert_eq!(None, array.get_mut(0, 3));
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_index_out_of_bounds() {
+    let array = construct_regular_matrix(4, 3);
+
+    let _value = array[(4, 0)];
+  }
+
+  #[test]
+  fn test_rows_and_columns() {
+    let mut array = construct_regular_matrix(4, 3);
+
+    assert_eq!(&[4, 5, 6, 7], array.row(1));
+    assert_eq!(vec![1, 5, 9], array.column(1).cloned().collect::<Vec<usize>>());
+
+    let rows: Vec<&[usize]> = array.rows().collect();
+    assert_eq!(vec![&[0, 1, 2, 3][..], &[4, 5, 6, 7][..], &[8, 9, 10, 11][..]], rows);
+
+    let columns: Vec<Vec<usize>> = array.columns().map(|c| c.cloned().collect()).collect();
+    assert_eq!(vec![vec![0, 4, 8], vec![1, 5, 9], vec![2, 6, 10], vec![3, 7, 11]], columns);
+
+    for value in array.column_mut(3) {
+      *value = 0;
+    }
+    array.row_mut(0)[0] = 42;
+
+    assert_eq!(&[42, 1, 2, 0], array.row(0));
+    assert_eq!(vec![0, 0, 0], array.column(3).cloned().collect::<Vec<usize>>());
+  }
+
+  #[test]
+  fn test_iter_enumerated() {
+    let array = construct_regular_matrix(3, 2);
+
+    for ((x, y), &value) in array.iter_enumerated() {
+      assert_eq!(array.get(x, y), value);
+    }
+
+    assert_eq!(6, array.iter_enumerated().count());
+  }
+
+  #[test]
+  fn test_map() {
+    let array = construct_regular_matrix(3, 2).map(|v| v.to_string());
+
+    assert_eq!(3, array.get_width());
+    assert_eq!(2, array.get_height());
+    assert_eq!("5", array[(2, 1)]);
+  }
+
+  #[test]
+  fn test_transpose() {
+    let array = construct_regular_matrix(3, 2);
+    let transposed = array.transpose();
+
+    assert_eq!(2, transposed.get_width());
+    assert_eq!(3, transposed.get_height());
+    assert_eq!(vec![0, 3, 1, 4, 2, 5], transposed.into_vec());
+  }
+
+  #[test]
+  fn test_rotate() {
+    // 0 1 2
+    // 3 4 5
+    let array = construct_regular_matrix(3, 2);
+
+    assert_eq!(Flat2dArray::from(vec![vec![3, 0], vec![4, 1], vec![5, 2]]), array.rotate_90());
+    assert_eq!(Flat2dArray::from(vec![vec![5, 4, 3], vec![2, 1, 0]]), array.rotate_180());
+    assert_eq!(Flat2dArray::from(vec![vec![2, 5], vec![1, 4], vec![0, 3]]), array.rotate_270());
+
+    assert_eq!(array, array.rotate_90().rotate_270());
+    assert_eq!(array.rotate_180(), array.rotate_90().rotate_90());
+  }
+
+  #[test]
+  fn test_flip() {
+    let array = construct_regular_matrix(3, 2);
+
+    assert_eq!(Flat2dArray::from(vec![vec![2, 1, 0], vec![5, 4, 3]]), array.flip_horizontal());
+    assert_eq!(Flat2dArray::from(vec![vec![3, 4, 5], vec![0, 1, 2]]), array.flip_vertical());
+    assert_eq!(array.rotate_180(), array.flip_horizontal().flip_vertical());
+  }
+
+  #[test]
+  fn test_non_copy() {
+    let mut array: Flat2dArray<String> = Flat2dArray::new_default(2, 2);
+
+    array.set(0, 0, "a".to_string());
+    array[(1, 1)].push_str("bc");
+
+    assert_eq!(vec!["a", "", "", "bc"], array.as_slice());
+
+    let vecs = Flat2dArray::from(vec![vec![vec![1], vec![2, 2]], vec![vec![], vec![4]]]);
+    let obliqued = oblique(&vecs);
+
+    assert_eq!(vec![vec![vec![1]], vec![vec![2, 2], vec![]], vec![vec![4]]], obliqued);
+    assert_eq!(vecs, de_oblique_square(&obliqued).unwrap());
+  }
+
+  #[test]
+  fn test_from_vec_checked() {
+    assert!(Flat2dArray::from_vec(2, 3, vec![0; 6]).is_ok());
+    assert!(Flat2dArray::from_vec(2, 3, vec![0; 5]).is_err());
+
+    assert_eq!(Ok(construct_regular_matrix(2, 2)), Flat2dArray::from_rows(vec![vec![0, 1], vec![2, 3]]));
+    assert_eq!(Err("row 2 has 1 cells, expected 2".to_string()), Flat2dArray::from_rows(vec![vec![0, 1], vec![2]]));
+    assert_eq!(Ok(Flat2dArray::new_zero_sized()), Flat2dArray::<usize>::from_rows(Vec::new()));
+  }
+
+}
@@ -0,0 +1,414 @@
+// Row-major array of any number of dimensions.
+//
+// Indices are given outermost axis first, the last axis is the one that is
+// contiguous in memory. `Flat2dArray` is the two dimensional case with the
+// shape [height, width].
+
+use std::ops::{
+  Index,
+  IndexMut,
+  Range
+};
+
+use super::traversal::{
+  DiagonalKind,
+  Diagonals
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlatNdArray<T, const N: usize> {
+  data: Vec<T>,
+  shape: [usize; N],
+  strides: [usize; N]
+}
+
+fn strides_of<const N: usize>(shape: &[usize; N]) -> [usize; N] {
+  let mut strides = [1; N];
+
+  for axis in (0 .. N.saturating_sub(1)).rev() {
+    strides[axis] = strides[axis + 1] * shape[axis + 1];
+  }
+
+  strides
+}
+
+fn assert_axis<const N: usize>(axis: usize) {
+  if axis >= N {
+    panic!("Invalid axis: {}", axis);
+  }
+}
+
+impl<T: Clone, const N: usize> FlatNdArray<T, N> {
+  pub fn from_elem(shape: [usize; N], value: T) -> FlatNdArray<T, N> {
+    FlatNdArray {
+      data: vec![value; shape.iter().product()],
+      shape,
+      strides: strides_of(&shape)
+    }
+  }
+
+  // keeps all axes, `axis` is narrowed down to `range`
+  pub fn slice(&self, axis: usize, range: Range<usize>) -> Result<FlatNdArray<T, N>, String> {
+    assert_axis::<N>(axis);
+
+    if range.start > range.end || range.end > self.shape[axis] {
+      return Err(format!("range {:?} exceeds axis {} of length {}", range, axis, self.shape[axis]));
+    }
+
+    let mut shape = self.shape;
+    shape[axis] = range.len();
+
+    Ok(FlatNdArray::from_shape_fn(shape, |mut index| {
+      index[axis] += range.start;
+      self[index].clone()
+    }))
+  }
+
+  // drops `axis` by fixing it at `position`, `M` has to be one less than `N`
+  pub fn slice_at<const M: usize>(&self, axis: usize, position: usize) -> Result<FlatNdArray<T, M>, String> {
+    assert_axis::<N>(axis);
+
+    if M + 1 != N {
+      return Err(format!("slicing a {} dimensional array gives {} dimensions, not {}", N, N - 1, M));
+    }
+
+    if position >= self.shape[axis] {
+      return Err(format!("position {} exceeds axis {} of length {}", position, axis, self.shape[axis]));
+    }
+
+    let mut shape = [0; M];
+    for (i, len) in self.shape.iter().enumerate().filter(|&(i, _)| i != axis) {
+      shape[if i < axis { i } else { i - 1 }] = *len;
+    }
+
+    Ok(FlatNdArray::from_shape_fn(shape, |reduced| {
+      let mut index = [0; N];
+
+      for (i, value) in index.iter_mut().enumerate() {
+        *value = match i {
+          i if i < axis => reduced[i],
+          i if i == axis => position,
+          i => reduced[i - 1]
+        };
+      }
+
+      self[index].clone()
+    }))
+  }
+
+  // the diagonals of the plane spanned by `axes` through `base`, in the same
+  // order as `oblique` gives them for a 2d array
+  pub fn oblique_along(&self, axes: (usize, usize), base: [usize; N]) -> Vec<Vec<T>> {
+    self.plane_diagonals(DiagonalKind::Anti, axes, base)
+      .map(|line| line.map(|index| self[index].clone()).collect())
+      .collect()
+  }
+}
+
+impl<T, const N: usize> FlatNdArray<T, N> {
+  pub fn from_shape_fn<F>(shape: [usize; N], mut f: F) -> FlatNdArray<T, N>
+  where F: FnMut([usize; N]) -> T {
+    FlatNdArray {
+      data: Indices::new(shape).map(&mut f).collect(),
+      shape,
+      strides: strides_of(&shape)
+    }
+  }
+
+  pub fn from_shape_vec(shape: [usize; N], data: Vec<T>) -> Result<FlatNdArray<T, N>, String> {
+    let len: usize = shape.iter().product();
+
+    if data.len() != len {
+      return Err(format!("expected {} elements for shape {:?}, got {}", len, shape, data.len()));
+    }
+
+    Ok(FlatNdArray {
+      data,
+      shape,
+      strides: strides_of(&shape)
+    })
+  }
+
+  pub fn into_vec(self) -> Vec<T> {
+    self.data
+  }
+
+  pub fn shape(&self) -> [usize; N] {
+    self.shape
+  }
+
+  pub fn strides(&self) -> [usize; N] {
+    self.strides
+  }
+
+  pub fn len(&self) -> usize {
+    self.data.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  pub fn contains(&self, index: [usize; N]) -> bool {
+    index.iter().zip(self.shape.iter()).all(|(i, len)| i < len)
+  }
+
+  pub fn offset_of(&self, index: [usize; N]) -> Option<usize> {
+    if !self.contains(index) {
+      return None;
+    }
+
+    Some(index.iter().zip(self.strides.iter()).map(|(i, stride)| i * stride).sum())
+  }
+
+  fn get_offset(&self, index: [usize; N]) -> usize {
+    match self.offset_of(index) {
+      Some(offset) => offset,
+      None => panic!("Out of bounds: {:?}", index)
+    }
+  }
+
+  pub fn get_at(&self, index: [usize; N]) -> Option<&T> {
+    self.offset_of(index).map(|offset| &self.data[offset])
+  }
+
+  pub fn get_at_mut(&mut self, index: [usize; N]) -> Option<&mut T> {
+    self.offset_of(index).map(move |offset| &mut self.data[offset])
+  }
+
+  pub fn as_slice(&self) -> &[T] {
+    &self.data
+  }
+
+  pub fn as_mut_slice(&mut self) -> &mut [T] {
+    &mut self.data
+  }
+
+  pub fn iter(&self) -> std::slice::Iter<'_, T> {
+    self.data.iter()
+  }
+
+  pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+    self.data.iter_mut()
+  }
+
+  pub fn indices(&self) -> Indices<N> {
+    Indices::new(self.shape)
+  }
+
+  pub fn iter_indexed(&self) -> impl Iterator<Item = ([usize; N], &T)> {
+    self.indices().zip(self.data.iter())
+  }
+
+  pub fn map<U, F>(&self, f: F) -> FlatNdArray<U, N>
+  where F: FnMut(&T) -> U {
+    FlatNdArray {
+      data: self.data.iter().map(f).collect(),
+      shape: self.shape,
+      strides: self.strides
+    }
+  }
+
+  // the elements along `axis` through `base`, the position of `base` on
+  // that axis is ignored
+  pub fn lane(&self, axis: usize, base: [usize; N]) -> impl Iterator<Item = &T> {
+    assert_axis::<N>(axis);
+
+    let mut start = base;
+    start[axis] = 0;
+
+    let offset = if self.shape[axis] == 0 { 0 } else { self.get_offset(start) };
+
+    self.data[offset..].iter().step_by(self.strides[axis]).take(self.shape[axis])
+  }
+
+  // diagonals of the plane spanned by `axes`, the first axis runs like x and
+  // the second like y of a 2d array; all other positions come from `base`
+  pub fn plane_diagonals(&self, kind: DiagonalKind, (axis_x, axis_y): (usize, usize), base: [usize; N]) -> impl Iterator<Item = impl Iterator<Item = [usize; N]>> {
+    assert_axis::<N>(axis_x);
+    assert_axis::<N>(axis_y);
+
+    if axis_x == axis_y {
+      panic!("Invalid axes: {}, {}", axis_x, axis_y);
+    }
+
+    let mut base = base;
+    base[axis_x] = 0;
+    base[axis_y] = 0;
+
+    if !self.contains(base) && self.shape[axis_x] > 0 && self.shape[axis_y] > 0 {
+      panic!("Out of bounds: {:?}", base);
+    }
+
+    Diagonals::new(self.shape[axis_x], self.shape[axis_y], kind).map(move |line| {
+      line.map(move |(x, y)| {
+        let mut index = base;
+        index[axis_x] = x;
+        index[axis_y] = y;
+        index
+      })
+    })
+  }
+}
+
+impl<T, const N: usize> Index<[usize; N]> for FlatNdArray<T, N> {
+  type Output = T;
+
+  fn index(&self, index: [usize; N]) -> &T {
+    let offset = self.get_offset(index);
+
+    &self.data[offset]
+  }
+}
+
+impl<T, const N: usize> IndexMut<[usize; N]> for FlatNdArray<T, N> {
+  fn index_mut(&mut self, index: [usize; N]) -> &mut T {
+    let offset = self.get_offset(index);
+
+    &mut self.data[offset]
+  }
+}
+
+// all indices of a shape in row-major order
+#[derive(Clone, Debug)]
+pub struct Indices<const N: usize> {
+  shape: [usize; N],
+  next: Option<[usize; N]>
+}
+
+impl<const N: usize> Indices<N> {
+  pub fn new(shape: [usize; N]) -> Indices<N> {
+    let empty = shape.contains(&0);
+
+    Indices {
+      shape,
+      next: if empty { None } else { Some([0; N]) }
+    }
+  }
+}
+
+impl<const N: usize> Iterator for Indices<N> {
+  type Item = [usize; N];
+
+  fn next(&mut self) -> Option<[usize; N]> {
+    let current = self.next?;
+    let mut following = current;
+
+    self.next = None;
+
+    for axis in (0..N).rev() {
+      following[axis] += 1;
+
+      if following[axis] < self.shape[axis] {
+        self.next = Some(following);
+        break;
+      }
+
+      following[axis] = 0;
+    }
+
+    Some(current)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use dp256::*;
+  use dp256::nd::*;
+  use dp256::traversal::DiagonalKind;
+
+  fn cube() -> FlatNdArray<usize, 3> {
+    FlatNdArray::from_shape_fn([2, 3, 4], |[z, y, x]| 100 * z + 10 * y + x)
+  }
+
+  #[test]
+  fn test_layout() {
+    let array = cube();
+
+    assert_eq!([2, 3, 4], array.shape());
+    assert_eq!([12, 4, 1], array.strides());
+    assert_eq!(24, array.len());
+    assert_eq!(123, array[[1, 2, 3]]);
+    assert_eq!(Some(23), array.offset_of([1, 2, 3]));
+    assert_eq!(None, array.get_at([2, 0, 0]));
+    assert_eq!(array.indices().collect::<Vec<_>>(), array.iter_indexed().map(|(index, _)| index).collect::<Vec<_>>());
+    assert!(array.iter_indexed().all(|([z, y, x], &v)| v == 100 * z + 10 * y + x));
+
+    let scalar: FlatNdArray<u8, 0> = FlatNdArray::from_elem([], 7);
+    assert_eq!(7, scalar[[]]);
+
+    assert!(FlatNdArray::from_shape_vec([2, 2], vec![1, 2, 3]).is_err());
+    assert_eq!(0, FlatNdArray::<u8, 3>::from_elem([2, 0, 3], 0).indices().count());
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_out_of_bounds() {
+    let _value = cube()[[0, 3, 0]];
+  }
+
+  #[test]
+  fn test_slice() {
+    let array = cube();
+
+    let sliced = array.slice(2, 1..3).unwrap();
+    assert_eq!([2, 3, 2], sliced.shape());
+    assert_eq!(111, sliced[[1, 1, 0]]);
+    assert!(array.slice(1, 2..4).is_err());
+
+    let plane: FlatNdArray<usize, 2> = array.slice_at(1, 2).unwrap();
+    assert_eq!([2, 4], plane.shape());
+    assert_eq!(vec![20, 21, 22, 23, 120, 121, 122, 123], plane.into_vec());
+
+    assert!(array.slice_at::<3>(0, 0).is_err());
+    assert!(array.slice_at::<2>(0, 2).is_err());
+
+    assert_eq!(vec![3, 13, 23], array.lane(1, [0, 0, 3]).cloned().collect::<Vec<_>>());
+    assert_eq!(vec![12, 112], array.lane(0, [1, 1, 2]).cloned().collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn test_plane_diagonals() {
+    let array = cube();
+
+    // the plane of z and x at y = 1
+    let obliqued = array.oblique_along((0, 2), [0, 1, 0]);
+    assert_eq!(vec![vec![10], vec![110, 11], vec![111, 12], vec![112, 13], vec![113]], obliqued);
+
+    let main: Vec<Vec<[usize; 3]>> = array.plane_diagonals(DiagonalKind::Main, (2, 1), [1, 0, 0])
+      .map(|line| line.collect())
+      .collect();
+    assert_eq!(6, main.len());
+    assert!(main.iter().flatten().all(|index| index[0] == 1));
+  }
+
+  #[test]
+  fn test_oblique_matches_2d() {
+    for width in 1..6 {
+      for height in 1..6 {
+        let array = construct_regular_matrix(width, height);
+
+        assert_eq!(oblique(&array), array.oblique_along((1, 0), [0, 0]));
+      }
+    }
+  }
+
+  #[test]
+  fn test_oblique_golden() {
+    // expected values are the output of the original 2-D implementation
+    let wide = construct_regular_matrix(4, 3);
+    let obliqued = vec![vec![0], vec![1, 4], vec![2, 5, 8], vec![3, 6, 9], vec![7, 10], vec![11]];
+
+    assert_eq!(obliqued, wide.oblique_along((1, 0), [0, 0]));
+    assert_eq!(obliqued, oblique(&wide));
+    assert_eq!(wide, de_oblique(&obliqued, 4, 3).unwrap());
+
+    let tall = construct_regular_matrix(2, 5);
+    let obliqued = vec![vec![0], vec![1, 2], vec![3, 4], vec![5, 6], vec![7, 8], vec![9]];
+
+    assert_eq!(obliqued, tall.oblique_along((1, 0), [0, 0]));
+    assert_eq!(obliqued, oblique(&tall));
+    assert_eq!(tall, de_oblique(&obliqued, 2, 5).unwrap());
+  }
+
+}
@@ -0,0 +1,138 @@
+// Neighbor iteration with a policy for the borders of the grid.
+
+use super::Flat2dArray;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Neighborhood {
+  // 4 neighbors, orthogonal only
+  VonNeumann,
+  // 8 neighbors, orthogonal and diagonal
+  Moore
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bounds {
+  // neighbors outside of the grid are skipped
+  Clip,
+  // neighbors wrap around to the opposite border
+  Torus
+}
+
+const VON_NEUMANN_OFFSETS: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+const MOORE_OFFSETS: [(isize, isize); 8] = [
+  (-1, -1), (0, -1), (1, -1),
+  (-1, 0), (1, 0),
+  (-1, 1), (0, 1), (1, 1)
+];
+
+impl Neighborhood {
+  pub fn offsets(&self) -> &'static [(isize, isize)] {
+    match *self {
+      Neighborhood::VonNeumann => &VON_NEUMANN_OFFSETS,
+      Neighborhood::Moore => &MOORE_OFFSETS
+    }
+  }
+}
+
+// on a torus narrower than three cells the same neighbor can show up more
+// than once, or be the cell itself
+#[derive(Clone, Debug)]
+pub struct Neighbors {
+  width: usize,
+  height: usize,
+  x: usize,
+  y: usize,
+  offsets: &'static [(isize, isize)],
+  bounds: Bounds,
+  index: usize
+}
+
+fn shift(value: usize, offset: isize, len: usize, bounds: Bounds) -> Option<usize> {
+  let shifted = value as isize + offset;
+
+  match bounds {
+    Bounds::Clip if shifted < 0 || shifted >= len as isize => None,
+    Bounds::Clip => Some(shifted as usize),
+    Bounds::Torus => Some(shifted.rem_euclid(len as isize) as usize)
+  }
+}
+
+impl Iterator for Neighbors {
+  type Item = (usize, usize);
+
+  fn next(&mut self) -> Option<(usize, usize)> {
+    while self.index < self.offsets.len() {
+      let (dx, dy) = self.offsets[self.index];
+      self.index += 1;
+
+      let x = shift(self.x, dx, self.width, self.bounds);
+      let y = shift(self.y, dy, self.height, self.bounds);
+
+      if let (Some(x), Some(y)) = (x, y) {
+        return Some((x, y));
+      }
+    }
+
+    None
+  }
+}
+
+pub fn neighbors(width: usize, height: usize, (x, y): (usize, usize), neighborhood: Neighborhood, bounds: Bounds) -> Neighbors {
+  if x >= width || y >= height {
+    panic!("Out of bounds: {}, {}", x, y);
+  }
+
+  Neighbors {
+    width,
+    height,
+    x,
+    y,
+    offsets: neighborhood.offsets(),
+    bounds,
+    index: 0
+  }
+}
+
+impl<T> Flat2dArray<T> {
+  pub fn neighbors(&self, x: usize, y: usize, neighborhood: Neighborhood, bounds: Bounds) -> Neighbors {
+    neighbors(self.get_width(), self.get_height(), (x, y), neighborhood, bounds)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use dp256::*;
+  use dp256::neighborhood::*;
+
+  fn collect(neighbors: Neighbors) -> Vec<(usize, usize)> {
+    let mut result: Vec<(usize, usize)> = neighbors.collect();
+    result.sort();
+    result
+  }
+
+  #[test]
+  fn test_clip() {
+    let array: Flat2dArray<u8> = Flat2dArray::new_default(3, 3);
+
+    assert_eq!(vec![(0, 1), (1, 0)], collect(array.neighbors(0, 0, Neighborhood::VonNeumann, Bounds::Clip)));
+    assert_eq!(vec![(0, 1), (1, 0), (1, 1)], collect(array.neighbors(0, 0, Neighborhood::Moore, Bounds::Clip)));
+    assert_eq!(8, array.neighbors(1, 1, Neighborhood::Moore, Bounds::Clip).count());
+  }
+
+  #[test]
+  fn test_torus() {
+    let array: Flat2dArray<u8> = Flat2dArray::new_default(4, 3);
+
+    assert_eq!(vec![(0, 1), (0, 2), (1, 0), (3, 0)], collect(array.neighbors(0, 0, Neighborhood::VonNeumann, Bounds::Torus)));
+    assert_eq!(8, array.neighbors(3, 2, Neighborhood::Moore, Bounds::Torus).count());
+    assert!(array.neighbors(3, 2, Neighborhood::Moore, Bounds::Torus).any(|n| n == (0, 0)));
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_out_of_bounds() {
+    neighbors(3, 3, (3, 0), Neighborhood::Moore, Bounds::Clip);
+  }
+
+}
@@ -0,0 +1,321 @@
+// Flood fill, connected components and shortest paths on grids.
+//
+// Paths are returned as coordinate lists that include both the start and
+// the goal.
+
+use std::cmp::Reverse;
+use std::collections::{
+  BinaryHeap,
+  VecDeque
+};
+use std::ops::Add;
+
+use super::Flat2dArray;
+use super::neighborhood::{
+  Bounds,
+  Neighborhood,
+  neighbors
+};
+
+// all cells connected to `start` that hold the same value as it
+pub fn region<T>(array: &Flat2dArray<T>, start: (usize, usize), neighborhood: Neighborhood, bounds: Bounds) -> Vec<(usize, usize)>
+where T: PartialEq {
+  let mut visited: Flat2dArray<bool> = Flat2dArray::new(array.get_width(), array.get_height(), false);
+
+  region_unvisited(array, start, neighborhood, bounds, &mut visited)
+}
+
+// `region`, with the visited cells shared between calls
+fn region_unvisited<T>(array: &Flat2dArray<T>, start: (usize, usize), neighborhood: Neighborhood, bounds: Bounds, visited: &mut Flat2dArray<bool>) -> Vec<(usize, usize)>
+where T: PartialEq {
+  let width = array.get_width();
+  let height = array.get_height();
+  let value = &array[start];
+
+  let mut result = Vec::new();
+  let mut queue = VecDeque::new();
+
+  visited[start] = true;
+  queue.push_back(start);
+
+  while let Some(pos) = queue.pop_front() {
+    result.push(pos);
+
+    for next in neighbors(width, height, pos, neighborhood, bounds) {
+      if !visited[next] && array[next] == *value {
+        visited[next] = true;
+        queue.push_back(next);
+      }
+    }
+  }
+
+  result
+}
+
+pub fn flood_fill<T>(array: &mut Flat2dArray<T>, start: (usize, usize), neighborhood: Neighborhood, bounds: Bounds, value: T) -> Vec<(usize, usize)>
+where T: PartialEq + Clone {
+  let filled = region(array, start, neighborhood, bounds);
+
+  for &pos in &filled {
+    array[pos] = value.clone();
+  }
+
+  filled
+}
+
+// labels every connected region of equal values, cells rejected by `include`
+// stay unlabeled, returns the labels and the number of components
+pub fn label_components<T, F>(array: &Flat2dArray<T>, neighborhood: Neighborhood, bounds: Bounds, include: F) -> (Flat2dArray<Option<usize>>, usize)
+where T: PartialEq, F: Fn(&T) -> bool {
+  let mut labels: Flat2dArray<Option<usize>> = Flat2dArray::new(array.get_width(), array.get_height(), None);
+  let mut visited: Flat2dArray<bool> = Flat2dArray::new(array.get_width(), array.get_height(), false);
+  let mut count = 0;
+
+  for y in 0..array.get_height() {
+    for x in 0..array.get_width() {
+      if labels[(x, y)].is_some() || !include(&array[(x, y)]) {
+        continue;
+      }
+
+      for pos in region_unvisited(array, (x, y), neighborhood, bounds, &mut visited) {
+        labels[pos] = Some(count);
+      }
+
+      count += 1;
+    }
+  }
+
+  (labels, count)
+}
+
+fn trace_back(previous: &Flat2dArray<Option<(usize, usize)>>, start: (usize, usize), goal: (usize, usize)) -> Vec<(usize, usize)> {
+  let mut path = vec![goal];
+  let mut pos = goal;
+
+  while pos != start {
+    pos = previous[pos].unwrap();
+    path.push(pos);
+  }
+
+  path.reverse();
+  path
+}
+
+// fewest steps over cells accepted by `passable`
+pub fn bfs_path<T, F>(array: &Flat2dArray<T>, start: (usize, usize), goal: (usize, usize), neighborhood: Neighborhood, bounds: Bounds, passable: F) -> Option<Vec<(usize, usize)>>
+where F: Fn(&T) -> bool {
+  let width = array.get_width();
+  let height = array.get_height();
+
+  if array.is_out_of_bounds(start.0, start.1) || array.is_out_of_bounds(goal.0, goal.1) {
+    return None;
+  }
+
+  if !passable(&array[start]) || !passable(&array[goal]) {
+    return None;
+  }
+
+  let mut previous: Flat2dArray<Option<(usize, usize)>> = Flat2dArray::new(width, height, None);
+  let mut visited: Flat2dArray<bool> = Flat2dArray::new(width, height, false);
+  let mut queue = VecDeque::new();
+
+  visited[start] = true;
+  queue.push_back(start);
+
+  while let Some(pos) = queue.pop_front() {
+    if pos == goal {
+      return Some(trace_back(&previous, start, goal));
+    }
+
+    for next in neighbors(width, height, pos, neighborhood, bounds) {
+      if !visited[next] && passable(&array[next]) {
+        visited[next] = true;
+        previous[next] = Some(pos);
+        queue.push_back(next);
+      }
+    }
+  }
+
+  None
+}
+
+// `cost` is the price of entering a cell, None marks it as impassable, the
+// heuristic has to underestimate the remaining cost for the result to be optimal
+pub fn astar<C, F, H>((width, height): (usize, usize), start: (usize, usize), goal: (usize, usize), neighborhood: Neighborhood, bounds: Bounds, mut cost: F, heuristic: H) -> Option<(C, Vec<(usize, usize)>)>
+where C: Copy + Ord + Default + Add<Output = C>, F: FnMut((usize, usize)) -> Option<C>, H: Fn((usize, usize)) -> C {
+  if start.0 >= width || start.1 >= height || goal.0 >= width || goal.1 >= height {
+    return None;
+  }
+
+  let mut best: Flat2dArray<Option<C>> = Flat2dArray::new(width, height, None);
+  let mut previous: Flat2dArray<Option<(usize, usize)>> = Flat2dArray::new(width, height, None);
+  let mut queue = BinaryHeap::new();
+
+  best[start] = Some(C::default());
+  queue.push(Reverse((heuristic(start), C::default(), start)));
+
+  while let Some(Reverse((_, so_far, pos))) = queue.pop() {
+    if pos == goal {
+      return Some((so_far, trace_back(&previous, start, goal)));
+    }
+
+    if best[pos].is_some_and(|known| known < so_far) {
+      continue;
+    }
+
+    for next in neighbors(width, height, pos, neighborhood, bounds) {
+      let step = match cost(next) {
+        Some(step) => step,
+        None => continue
+      };
+
+      let total = so_far + step;
+
+      if best[next].is_none_or(|known| total < known) {
+        best[next] = Some(total);
+        previous[next] = Some(pos);
+        queue.push(Reverse((total + heuristic(next), total, next)));
+      }
+    }
+  }
+
+  None
+}
+
+pub fn dijkstra<C>(costs: &Flat2dArray<C>, start: (usize, usize), goal: (usize, usize), neighborhood: Neighborhood, bounds: Bounds) -> Option<(C, Vec<(usize, usize)>)>
+where C: Copy + Ord + Default + Add<Output = C> {
+  astar((costs.get_width(), costs.get_height()), start, goal, neighborhood, bounds, |pos| Some(costs[pos]), |_| C::default())
+}
+
+pub fn manhattan_distance(a: (usize, usize), b: (usize, usize)) -> usize {
+  a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+pub fn chebyshev_distance(a: (usize, usize), b: (usize, usize)) -> usize {
+  std::cmp::max(a.0.abs_diff(b.0), a.1.abs_diff(b.1))
+}
+
+#[cfg(test)]
+mod tests {
+  use dp256::*;
+  use dp256::neighborhood::*;
+  use dp256::search::*;
+
+  fn maze() -> Flat2dArray<char> {
+    "
+      . . . # .
+      # # . # .
+      . . . . .
+      . # # # .
+      . . . # .
+    ".parse().unwrap()
+  }
+
+  fn assert_connected(path: &[(usize, usize)], neighborhood: Neighborhood, bounds: Bounds) {
+    for pair in path.windows(2) {
+      assert!(neighbors(5, 5, pair[0], neighborhood, bounds).any(|n| n == pair[1]));
+    }
+  }
+
+  #[test]
+  fn test_flood_fill() {
+    let mut array = maze();
+
+    let filled = flood_fill(&mut array, (0, 0), Neighborhood::VonNeumann, Bounds::Clip, 'o');
+
+    assert_eq!(17, filled.len());
+    assert_eq!('o', array[(4, 4)]);
+    assert_eq!('#', array[(3, 0)]);
+
+    let mut array = maze();
+    let filled = flood_fill(&mut array, (3, 0), Neighborhood::Moore, Bounds::Clip, 'x');
+
+    assert_eq!(vec![(3, 0), (3, 1)], filled);
+  }
+
+  #[test]
+  fn test_label_components() {
+    let (labels, count) = label_components(&maze(), Neighborhood::VonNeumann, Bounds::Clip, |&c| c == '#');
+
+    assert_eq!(3, count);
+    assert_eq!(Some(0), labels[(3, 0)]);
+    assert_eq!(Some(0), labels[(3, 1)]);
+    assert_eq!(Some(1), labels[(0, 1)]);
+    assert_eq!(Some(2), labels[(1, 3)]);
+    assert_eq!(None, labels[(0, 0)]);
+
+    let diagonal: Flat2dArray<char> = "# .\n. #".parse().unwrap();
+
+    let (_, count) = label_components(&diagonal, Neighborhood::VonNeumann, Bounds::Clip, |&c| c == '#');
+    assert_eq!(2, count);
+
+    let (labels, count) = label_components(&diagonal, Neighborhood::Moore, Bounds::Clip, |&c| c == '#');
+    assert_eq!(1, count);
+    assert_eq!(Some(0), labels[(1, 1)]);
+
+    // every cell is a component of its own
+    let checkerboard = Flat2dArray::from_fn(300, 300, |x, y| (x + y) % 2);
+
+    let (labels, count) = label_components(&checkerboard, Neighborhood::VonNeumann, Bounds::Clip, |_| true);
+    assert_eq!(300 * 300, count);
+    assert_eq!(Some(300 * 300 - 1), labels[(299, 299)]);
+  }
+
+  #[test]
+  fn test_bfs_path() {
+    let path = bfs_path(&maze(), (0, 0), (4, 0), Neighborhood::VonNeumann, Bounds::Clip, |&c| c == '.').unwrap();
+
+    assert_eq!((0, 0), path[0]);
+    assert_eq!((4, 0), path[path.len() - 1]);
+    assert_eq!(9, path.len());
+    assert_connected(&path, Neighborhood::VonNeumann, Bounds::Clip);
+
+    let wrapped = bfs_path(&maze(), (0, 0), (4, 0), Neighborhood::VonNeumann, Bounds::Torus, |&c| c == '.').unwrap();
+
+    assert_eq!(vec![(0, 0), (4, 0)], wrapped);
+
+    assert!(bfs_path(&maze(), (0, 0), (3, 0), Neighborhood::VonNeumann, Bounds::Clip, |&c| c == '.').is_none());
+    assert!(bfs_path(&maze(), (9, 0), (4, 0), Neighborhood::VonNeumann, Bounds::Clip, |&c| c == '.').is_none());
+  }
+
+  #[test]
+  fn test_dijkstra() {
+    let costs: Flat2dArray<u64> = "
+      1 9 1 1 1
+      1 9 1 9 1
+      1 1 1 9 1
+    ".parse().unwrap();
+
+    let (cost, path) = dijkstra(&costs, (0, 0), (4, 2), Neighborhood::VonNeumann, Bounds::Clip).unwrap();
+
+    assert_eq!(10, cost);
+    assert_eq!(vec![(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (2, 1), (2, 0), (3, 0), (4, 0), (4, 1), (4, 2)], path);
+  }
+
+  #[test]
+  fn test_astar() {
+    let array = maze();
+    let goal = (0, 4);
+
+    let (cost, path) = astar((5, 5), (0, 0), goal, Neighborhood::VonNeumann, Bounds::Clip,
+      |pos| if array[pos] == '.' { Some(1usize) } else { None },
+      |pos| manhattan_distance(pos, goal)).unwrap();
+
+    let bfs = bfs_path(&array, (0, 0), goal, Neighborhood::VonNeumann, Bounds::Clip, |&c| c == '.').unwrap();
+
+    assert_eq!(bfs.len() - 1, cost);
+    assert_eq!(bfs.len(), path.len());
+    assert_connected(&path, Neighborhood::VonNeumann, Bounds::Clip);
+
+    let (cost, _) = astar((5, 5), (0, 0), (4, 4), Neighborhood::Moore, Bounds::Clip,
+      |_| Some(1usize),
+      |pos| chebyshev_distance(pos, (4, 4))).unwrap();
+
+    assert_eq!(4, cost);
+
+    assert!(astar((5, 5), (0, 0), (3, 0), Neighborhood::VonNeumann, Bounds::Clip,
+      |pos| if array[pos] == '.' { Some(1usize) } else { None },
+      |_| 0).is_none());
+  }
+
+}
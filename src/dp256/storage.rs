@@ -0,0 +1,396 @@
+// Interchangeable storage for large grids.
+//
+// All backends are read and written through `Grid` and `GridMut`. Cells that
+// were never written hold the default value given on construction, the
+// sparse and chunked backends only spend memory on the others.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::mem::size_of;
+
+use super::{
+  Flat2dArray,
+  Grid,
+  GridMut
+};
+
+pub const DEFAULT_CHUNK_SIZE: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MemoryUsage {
+  // cells that are backed by memory, written or not
+  pub stored_cells: usize,
+  // estimate of the heap and inline size in bytes
+  pub bytes: usize,
+  // what a dense array of the same size would take
+  pub dense_bytes: usize
+}
+
+impl fmt::Display for MemoryUsage {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} cells stored, {} bytes ({} bytes dense)", self.stored_cells, self.bytes, self.dense_bytes)
+  }
+}
+
+fn dense_bytes<T>(width: usize, height: usize) -> usize {
+  // saturates for grids too large to ever be stored densely
+  width.saturating_mul(height)
+    .saturating_mul(size_of::<T>())
+    .saturating_add(size_of::<Flat2dArray<T>>())
+}
+
+pub trait Storage: GridMut
+where Self::Item: Clone {
+  fn with_default(width: usize, height: usize, default: Self::Item) -> Self
+  where Self: Sized;
+
+  fn memory_usage(&self) -> MemoryUsage;
+
+  // copies every cell of `grid` that differs from `default`
+  fn from_grid<G>(grid: &G, default: Self::Item) -> Self
+  where Self: Sized, G: Grid<Item = Self::Item>, Self::Item: PartialEq {
+    let mut storage = Self::with_default(grid.get_width(), grid.get_height(), default.clone());
+
+    for y in 0..grid.get_height() {
+      for x in 0..grid.get_width() {
+        let value = grid.get_ref(x, y).unwrap();
+
+        if *value != default {
+          storage.set(x, y, value.clone());
+        }
+      }
+    }
+
+    storage
+  }
+
+  fn to_dense(&self) -> Flat2dArray<Self::Item> {
+    Flat2dArray::from_fn(self.get_width(), self.get_height(), |x, y| self.get_ref(x, y).unwrap().clone())
+  }
+
+  // every cell in row-major order
+  fn cells(&self) -> impl Iterator<Item = ((usize, usize), &Self::Item)> {
+    let width = self.get_width();
+    let coords = (0 .. width * self.get_height()).map(move |i| (i % width, i / width));
+
+    coords.clone().zip(self.traverse(coords))
+  }
+}
+
+impl<T: Clone> Storage for Flat2dArray<T> {
+  fn with_default(width: usize, height: usize, default: T) -> Flat2dArray<T> {
+    Flat2dArray::new(width, height, default)
+  }
+
+  fn memory_usage(&self) -> MemoryUsage {
+    let dense = dense_bytes::<T>(self.get_width(), self.get_height());
+
+    MemoryUsage {
+      stored_cells: self.len(),
+      bytes: dense,
+      dense_bytes: dense
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct SparseGrid<T> {
+  width: usize,
+  height: usize,
+  default: T,
+  cells: HashMap<(usize, usize), T>
+}
+
+impl<T> SparseGrid<T> {
+  pub fn new(width: usize, height: usize, default: T) -> SparseGrid<T> {
+    SparseGrid {
+      width,
+      height,
+      default,
+      cells: HashMap::new()
+    }
+  }
+
+  pub fn get_default(&self) -> &T {
+    &self.default
+  }
+
+  // only the cells that hold something other than the default
+  pub fn iter_stored(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+    self.cells.iter().map(|(&pos, value)| (pos, value))
+  }
+}
+
+impl<T: PartialEq> SparseGrid<T> {
+  // drops cells that were written back to the default through `get_mut`
+  pub fn compact(&mut self) {
+    let default = &self.default;
+    self.cells.retain(|_, value| value != default);
+  }
+}
+
+impl<T> Grid for SparseGrid<T> {
+  type Item = T;
+
+  fn get_width(&self) -> usize {
+    self.width
+  }
+
+  fn get_height(&self) -> usize {
+    self.height
+  }
+
+  fn get_ref(&self, x: usize, y: usize) -> Option<&T> {
+    if self.is_out_of_bounds(x, y) {
+      return None;
+    }
+
+    Some(self.cells.get(&(x, y)).unwrap_or(&self.default))
+  }
+}
+
+impl<T: Clone + PartialEq> GridMut for SparseGrid<T> {
+  fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+    if self.is_out_of_bounds(x, y) {
+      return None;
+    }
+
+    let default = &self.default;
+    Some(self.cells.entry((x, y)).or_insert_with(|| default.clone()))
+  }
+
+  fn set(&mut self, x: usize, y: usize, val: T) {
+    if self.is_out_of_bounds(x, y) {
+      panic!("Out of bounds: {}, {}", x, y);
+    }
+
+    if val == self.default {
+      self.cells.remove(&(x, y));
+    } else {
+      self.cells.insert((x, y), val);
+    }
+  }
+}
+
+impl<T: Clone + PartialEq> Storage for SparseGrid<T> {
+  fn with_default(width: usize, height: usize, default: T) -> SparseGrid<T> {
+    SparseGrid::new(width, height, default)
+  }
+
+  fn memory_usage(&self) -> MemoryUsage {
+    // one control byte per bucket on top of the entries
+    let bucket = size_of::<((usize, usize), T)>() + 1;
+
+    MemoryUsage {
+      stored_cells: self.cells.len(),
+      bytes: size_of::<SparseGrid<T>>() + self.cells.capacity() * bucket,
+      dense_bytes: dense_bytes::<T>(self.width, self.height)
+    }
+  }
+}
+
+// square tiles that are allocated on the first write into them, keyed by
+// their chunk column and row
+#[derive(Clone, Debug)]
+pub struct ChunkedGrid<T> {
+  width: usize,
+  height: usize,
+  chunk_size: usize,
+  default: T,
+  chunks: HashMap<(usize, usize), Vec<T>>
+}
+
+impl<T> ChunkedGrid<T> {
+  pub fn with_chunk_size(width: usize, height: usize, chunk_size: usize, default: T) -> ChunkedGrid<T> {
+    assert!(chunk_size > 0);
+
+    ChunkedGrid {
+      width,
+      height,
+      chunk_size,
+      default,
+      chunks: HashMap::new()
+    }
+  }
+
+  pub fn get_chunk_size(&self) -> usize {
+    self.chunk_size
+  }
+
+  pub fn allocated_chunks(&self) -> usize {
+    self.chunks.len()
+  }
+
+  fn locate(&self, x: usize, y: usize) -> ((usize, usize), usize) {
+    let chunk = (x / self.chunk_size, y / self.chunk_size);
+
+    (chunk, x % self.chunk_size + (y % self.chunk_size) * self.chunk_size)
+  }
+}
+
+impl<T> Grid for ChunkedGrid<T> {
+  type Item = T;
+
+  fn get_width(&self) -> usize {
+    self.width
+  }
+
+  fn get_height(&self) -> usize {
+    self.height
+  }
+
+  fn get_ref(&self, x: usize, y: usize) -> Option<&T> {
+    if self.is_out_of_bounds(x, y) {
+      return None;
+    }
+
+    let (chunk, offset) = self.locate(x, y);
+
+    match self.chunks.get(&chunk) {
+      Some(cells) => Some(&cells[offset]),
+      None => Some(&self.default)
+    }
+  }
+}
+
+impl<T: Clone> GridMut for ChunkedGrid<T> {
+  fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+    if self.is_out_of_bounds(x, y) {
+      return None;
+    }
+
+    let (chunk, offset) = self.locate(x, y);
+    let cells_per_chunk = self.chunk_size * self.chunk_size;
+    let default = &self.default;
+
+    let cells = self.chunks.entry(chunk).or_insert_with(|| vec![default.clone(); cells_per_chunk]);
+
+    Some(&mut cells[offset])
+  }
+}
+
+impl<T: Clone> Storage for ChunkedGrid<T> {
+  fn with_default(width: usize, height: usize, default: T) -> ChunkedGrid<T> {
+    ChunkedGrid::with_chunk_size(width, height, DEFAULT_CHUNK_SIZE, default)
+  }
+
+  fn memory_usage(&self) -> MemoryUsage {
+    let stored_cells = self.allocated_chunks() * self.chunk_size * self.chunk_size;
+    // one control byte per bucket like in the sparse grid
+    let bucket = size_of::<((usize, usize), Vec<T>)>() + 1;
+
+    MemoryUsage {
+      stored_cells,
+      bytes: size_of::<ChunkedGrid<T>>() + self.chunks.capacity() * bucket + stored_cells * size_of::<T>(),
+      dense_bytes: dense_bytes::<T>(self.width, self.height)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use dp256::*;
+  use dp256::storage::*;
+
+  fn fill_diagonal<S: Storage<Item = u32>>(storage: &mut S) {
+    for i in 0..std::cmp::min(storage.get_width(), storage.get_height()) {
+      storage.set(i, i, i as u32 + 1);
+    }
+  }
+
+  fn check_backend<S: Storage<Item = u32>>() {
+    let mut storage = S::with_default(40, 30, 0);
+    fill_diagonal(&mut storage);
+
+    assert_eq!(Some(&5), storage.get_ref(4, 4));
+    assert_eq!(Some(&0), storage.get_ref(5, 4));
+    assert_eq!(None, storage.get_ref(40, 0));
+    assert_eq!(30, storage.cells().filter(|&(_, &v)| v != 0).count());
+    assert_eq!(Some(((29, 29), &30)), storage.cells().find(|&(_, &v)| v == 30));
+
+    *storage.get_mut(0, 29).unwrap() += 7;
+    assert_eq!(Some(&7), storage.get_ref(0, 29));
+
+    let mut dense: Flat2dArray<u32> = Flat2dArray::new(40, 30, 0);
+    fill_diagonal(&mut dense);
+    dense[(0, 29)] = 7;
+
+    assert_eq!(dense, storage.to_dense());
+  }
+
+  #[test]
+  fn test_backends() {
+    check_backend::<Flat2dArray<u32>>();
+    check_backend::<SparseGrid<u32>>();
+    check_backend::<ChunkedGrid<u32>>();
+  }
+
+  #[test]
+  fn test_conversion() {
+    let mut dense: Flat2dArray<u32> = Flat2dArray::new(100, 100, 0);
+    fill_diagonal(&mut dense);
+
+    let sparse = SparseGrid::from_grid(&dense, 0);
+    let chunked = ChunkedGrid::from_grid(&sparse, 0);
+    let back = Flat2dArray::from_grid(&chunked, 0);
+
+    assert_eq!(dense, back);
+    assert_eq!(100, sparse.iter_stored().count());
+    assert_eq!(7, chunked.allocated_chunks());
+  }
+
+  #[test]
+  fn test_sparse_default() {
+    let mut sparse = SparseGrid::new(10, 10, '.');
+
+    sparse.set(3, 3, '#');
+    sparse.set(3, 3, '.');
+    assert_eq!(0, sparse.iter_stored().count());
+
+    *sparse.get_mut(1, 1).unwrap() = '#';
+    *sparse.get_mut(1, 1).unwrap() = '.';
+    assert_eq!(1, sparse.iter_stored().count());
+
+    sparse.compact();
+    assert_eq!(0, sparse.iter_stored().count());
+  }
+
+  #[test]
+  fn test_memory_usage() {
+    let mut dense: Flat2dArray<u64> = Flat2dArray::new(1000, 1000, 0);
+    fill_diagonal_u64(&mut dense);
+
+    let sparse = SparseGrid::from_grid(&dense, 0);
+    let chunked: ChunkedGrid<u64> = ChunkedGrid::from_grid(&dense, 0);
+
+    assert_eq!(1_000_000, dense.memory_usage().stored_cells);
+    assert_eq!(dense.memory_usage().bytes, sparse.memory_usage().dense_bytes);
+    assert_eq!(1000, sparse.memory_usage().stored_cells);
+    assert_eq!(63 * 16 * 16, chunked.memory_usage().stored_cells);
+
+    assert!(sparse.memory_usage().bytes < dense.memory_usage().bytes / 100);
+    assert!(chunked.memory_usage().bytes < dense.memory_usage().bytes / 10);
+    assert!(dense.memory_usage().to_string().starts_with("1000000 cells stored"));
+  }
+
+  #[test]
+  fn test_huge_dimensions() {
+    let mut chunked = ChunkedGrid::with_chunk_size(usize::MAX, usize::MAX, 16, 0u8);
+
+    chunked.set(usize::MAX - 1, usize::MAX - 1, 1);
+    assert_eq!(Some(&1), chunked.get_ref(usize::MAX - 1, usize::MAX - 1));
+    assert_eq!(Some(&0), chunked.get_ref(0, 0));
+    assert_eq!(1, chunked.allocated_chunks());
+    assert_eq!(usize::MAX, chunked.memory_usage().dense_bytes);
+
+    let sparse = SparseGrid::new(usize::MAX, 2, 0u64);
+    assert_eq!(usize::MAX, sparse.memory_usage().dense_bytes);
+  }
+
+  fn fill_diagonal_u64(array: &mut Flat2dArray<u64>) {
+    for i in 0..array.get_width() {
+      array[(i, i)] = 1;
+    }
+  }
+
+}
@@ -0,0 +1,333 @@
+// Reading grids from text and configurable pretty printing.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::Flat2dArray;
+
+pub struct GridParser<T> {
+  delimiter: Option<char>,
+  fill: Option<T>
+}
+
+impl<T> Default for GridParser<T> {
+  fn default() -> GridParser<T> {
+    GridParser {
+      delimiter: None,
+      fill: None
+    }
+  }
+}
+
+impl<T> GridParser<T>
+where T: FromStr + Clone, T::Err: fmt::Display {
+  // cells are separated by whitespace unless a delimiter is given
+  pub fn new() -> GridParser<T> {
+    GridParser::default()
+  }
+
+  pub fn delimiter(mut self, delimiter: char) -> GridParser<T> {
+    self.delimiter = Some(delimiter);
+    self
+  }
+
+  // pads ragged rows and empty cells, without it both are errors
+  pub fn fill(mut self, fill: T) -> GridParser<T> {
+    self.fill = Some(fill);
+    self
+  }
+
+  fn parse_cell(&self, cell: &str, line_nr: usize, column: usize) -> Result<T, String> {
+    let trimmed = cell.trim();
+
+    if trimmed.is_empty() {
+      return self.fill.clone().ok_or(format!("line {}, column {}: empty cell", line_nr, column + 1));
+    }
+
+    trimmed.parse::<T>().map_err(|err| format!("line {}, column {}: {}", line_nr, column + 1, err))
+  }
+
+  fn parse_line(&self, line: &str, line_nr: usize) -> Result<Vec<T>, String> {
+    match self.delimiter {
+      Some(delimiter) => line.split(delimiter)
+        .enumerate()
+        .map(|(column, cell)| self.parse_cell(cell, line_nr, column))
+        .collect(),
+      None => line.split_whitespace()
+        .enumerate()
+        .map(|(column, cell)| self.parse_cell(cell, line_nr, column))
+        .collect()
+    }
+  }
+
+  // blank lines are skipped
+  pub fn parse(&self, s: &str) -> Result<Flat2dArray<T>, String> {
+    // with their line numbers
+    let mut rows: Vec<(usize, Vec<T>)> = Vec::new();
+
+    for (i, line) in s.lines().enumerate() {
+      if line.trim().is_empty() {
+        continue;
+      }
+
+      rows.push((i + 1, self.parse_line(line, i + 1)?));
+    }
+
+    let width = rows.iter().map(|(_, row)| row.len()).max().unwrap_or(0);
+
+    for (line_nr, row) in &mut rows {
+      if row.len() < width {
+        let fill = self.fill.clone().ok_or(format!("line {} has {} cells, expected {}", line_nr, row.len(), width))?;
+
+        row.resize(width, fill);
+      }
+    }
+
+    Flat2dArray::from_rows(rows.into_iter().map(|(_, row)| row).collect())
+  }
+}
+
+impl<T> FromStr for Flat2dArray<T>
+where T: FromStr + Clone, T::Err: fmt::Display {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Flat2dArray<T>, String> {
+    GridParser::new().parse(s)
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alignment {
+  Left,
+  Right,
+  Center
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColumnWidth {
+  // cells are padded to at least this width
+  Fixed(usize),
+  // every column gets the width of the widest cell of the grid
+  Auto,
+  // every column gets the width of its own widest cell
+  AutoPerColumn
+}
+
+#[derive(Clone, Debug)]
+pub struct GridFormatter {
+  alignment: Alignment,
+  column_width: ColumnWidth,
+  separator: String,
+  trailing_separator: bool,
+  header: bool
+}
+
+impl Default for GridFormatter {
+  fn default() -> GridFormatter {
+    GridFormatter {
+      alignment: Alignment::Right,
+      column_width: ColumnWidth::Fixed(3),
+      separator: " ".to_string(),
+      trailing_separator: true,
+      header: true
+    }
+  }
+}
+
+impl GridFormatter {
+  pub fn new() -> GridFormatter {
+    GridFormatter::default()
+  }
+
+  pub fn alignment(mut self, alignment: Alignment) -> GridFormatter {
+    self.alignment = alignment;
+    self
+  }
+
+  pub fn column_width(mut self, column_width: ColumnWidth) -> GridFormatter {
+    self.column_width = column_width;
+    self
+  }
+
+  pub fn separator<S: ToString>(mut self, separator: S) -> GridFormatter {
+    self.separator = separator.to_string();
+    self
+  }
+
+  // whether the last cell of a row is followed by the separator as well
+  pub fn trailing_separator(mut self, trailing_separator: bool) -> GridFormatter {
+    self.trailing_separator = trailing_separator;
+    self
+  }
+
+  // the "width: .. height: .." line in front of the cells
+  pub fn header(mut self, header: bool) -> GridFormatter {
+    self.header = header;
+    self
+  }
+
+  fn column_widths(&self, cells: &Flat2dArray<String>) -> Vec<usize> {
+    let cell_width = |cell: &String| cell.chars().count();
+
+    match self.column_width {
+      ColumnWidth::Fixed(width) => vec![width; cells.get_width()],
+      ColumnWidth::Auto => {
+        let width = cells.iter().map(cell_width).max().unwrap_or(0);
+        vec![width; cells.get_width()]
+      },
+      ColumnWidth::AutoPerColumn => cells.columns()
+        .map(|column| column.map(cell_width).max().unwrap_or(0))
+        .collect()
+    }
+  }
+
+  fn write_cell<W: fmt::Write>(&self, w: &mut W, cell: &str, width: usize) -> fmt::Result {
+    match self.alignment {
+      Alignment::Left => write!(w, "{:<width$}", cell, width = width),
+      Alignment::Right => write!(w, "{:>width$}", cell, width = width),
+      Alignment::Center => write!(w, "{:^width$}", cell, width = width)
+    }
+  }
+
+  pub fn write<T, W>(&self, w: &mut W, array: &Flat2dArray<T>) -> fmt::Result
+  where T: fmt::Display, W: fmt::Write {
+    if self.header {
+      writeln!(w, "width: {} height: {}", array.get_width(), array.get_height())?;
+    }
+
+    let cells = array.map(|value| value.to_string());
+    let widths = self.column_widths(&cells);
+
+    for row in cells.rows() {
+      for (x, cell) in row.iter().enumerate() {
+        if x > 0 {
+          w.write_str(&self.separator)?;
+        }
+
+        self.write_cell(w, cell, widths[x])?;
+      }
+
+      if self.trailing_separator && !row.is_empty() {
+        w.write_str(&self.separator)?;
+      }
+
+      writeln!(w)?;
+    }
+
+    Ok(())
+  }
+
+  pub fn format<T: fmt::Display>(&self, array: &Flat2dArray<T>) -> String {
+    let mut result = String::new();
+
+    self.write(&mut result, array).unwrap();
+
+    result
+  }
+}
+
+impl<T> fmt::Display for Flat2dArray<T>
+  where T: fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      GridFormatter::default().write(f, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+  use dp256::*;
+  use dp256::text::*;
+
+  #[test]
+  fn test_from_str() {
+    let array: Flat2dArray<usize> = "0 1 2\n3  4 5\n\n6\t7 8\n".parse().unwrap();
+
+    assert_eq!(construct_regular_matrix(3, 3), array);
+  }
+
+  #[test]
+  fn test_from_str_empty() {
+    let array: Flat2dArray<usize> = "\n  \n".parse().unwrap();
+
+    assert_eq!(0, array.get_width());
+    assert_eq!(0, array.get_height());
+  }
+
+  #[test]
+  fn test_from_str_invalid() {
+    assert_eq!(Err("line 3 has 1 cells, expected 2".to_string()), "1 2\n\n3".parse::<Flat2dArray<usize>>());
+
+    let err = "1 2\n3 x".parse::<Flat2dArray<usize>>().unwrap_err();
+    assert!(err.starts_with("line 2, column 2"));
+  }
+
+  #[test]
+  fn test_delimiter_and_fill() {
+    let array = GridParser::new()
+      .delimiter(',')
+      .fill(0)
+      .parse("1, 2, 3\n4,,6\n7")
+      .unwrap();
+
+    assert_eq!(Flat2dArray::from(vec![vec![1, 2, 3], vec![4, 0, 6], vec![7, 0, 0]]), array);
+
+    assert!(GridParser::<i32>::new().delimiter(',').parse("1,,3").is_err());
+  }
+
+  #[test]
+  fn test_parse_strings() {
+    let array: Flat2dArray<String> = "a bb\nccc d".parse().unwrap();
+
+    assert_eq!("ccc", array[(0, 1)]);
+  }
+
+  #[test]
+  fn test_display() {
+    let array = construct_regular_matrix(3, 2);
+
+    assert_eq!("width: 3 height: 2\n  0   1   2 \n  3   4   5 \n", array.to_string());
+  }
+
+  #[test]
+  fn test_formatter() {
+    let array = Flat2dArray::from(vec![vec![1, 200, 3], vec![40, 5, 6]]);
+
+    let auto = GridFormatter::new()
+      .header(false)
+      .trailing_separator(false)
+      .column_width(ColumnWidth::Auto)
+      .alignment(Alignment::Left)
+      .separator("|")
+      .format(&array);
+
+    assert_eq!("1  |200|3  \n40 |5  |6  \n", auto);
+
+    let per_column = GridFormatter::new()
+      .header(false)
+      .trailing_separator(false)
+      .column_width(ColumnWidth::AutoPerColumn)
+      .format(&array);
+
+    assert_eq!(" 1 200 3\n40   5 6\n", per_column);
+
+    let centered = GridFormatter::new()
+      .column_width(ColumnWidth::Fixed(4))
+      .alignment(Alignment::Center)
+      .separator(", ")
+      .trailing_separator(false)
+      .format(&array);
+
+    assert_eq!("width: 3 height: 2\n 1  , 200 ,  3  \n 40 ,  5  ,  6  \n", centered);
+  }
+
+  #[test]
+  fn test_round_trip() {
+    let array = construct_regular_matrix(5, 4);
+
+    let text = GridFormatter::new().header(false).separator(";").trailing_separator(false).format(&array);
+    let parsed = GridParser::new().delimiter(';').parse(&text).unwrap();
+
+    assert_eq!(array, parsed);
+  }
+
+}
@@ -0,0 +1,529 @@
+// Lazy traversal orders over a width x height grid.
+//
+// Every traversal yields (x, y) coordinates and never allocates, so it can be
+// used with any grid of that shape. `Grid::traverse` turns the coordinates
+// into element references and `GridMut::write_traversal` is the inverse that
+// writes a sequence back in the same order.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiagonalKind {
+  // x + y is constant, each line runs from top right to bottom left
+  Anti,
+  // x - y is constant, each line runs from top left to bottom right
+  Main
+}
+
+#[derive(Clone, Debug)]
+pub struct DiagonalLine {
+  start_x: usize,
+  start_y: usize,
+  kind: DiagonalKind,
+  reversed: bool,
+  front: usize,
+  back: usize
+}
+
+impl DiagonalLine {
+  pub fn reversed(self) -> DiagonalLine {
+    DiagonalLine {
+      reversed: !self.reversed,
+      ..self
+    }
+  }
+
+  fn coord_at(&self, index: usize) -> (usize, usize) {
+    match self.kind {
+      DiagonalKind::Anti => (self.start_x - index, self.start_y + index),
+      DiagonalKind::Main => (self.start_x + index, self.start_y + index)
+    }
+  }
+
+  fn take_front(&mut self) -> (usize, usize) {
+    let coord = self.coord_at(self.front);
+    self.front += 1;
+    coord
+  }
+
+  fn take_back(&mut self) -> (usize, usize) {
+    self.back -= 1;
+    self.coord_at(self.back)
+  }
+}
+
+impl Iterator for DiagonalLine {
+  type Item = (usize, usize);
+
+  fn next(&mut self) -> Option<(usize, usize)> {
+    if self.front >= self.back {
+      return None;
+    }
+
+    if self.reversed {
+      Some(self.take_back())
+    } else {
+      Some(self.take_front())
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let len = self.back - self.front;
+    (len, Some(len))
+  }
+}
+
+impl DoubleEndedIterator for DiagonalLine {
+  fn next_back(&mut self) -> Option<(usize, usize)> {
+    if self.front >= self.back {
+      return None;
+    }
+
+    if self.reversed {
+      Some(self.take_front())
+    } else {
+      Some(self.take_back())
+    }
+  }
+}
+
+impl ExactSizeIterator for DiagonalLine {}
+
+#[derive(Clone, Debug)]
+pub struct Diagonals {
+  width: usize,
+  height: usize,
+  kind: DiagonalKind,
+  front: usize,
+  back: usize
+}
+
+impl Diagonals {
+  pub fn new(width: usize, height: usize, kind: DiagonalKind) -> Diagonals {
+    let count = if width == 0 || height == 0 { 0 } else { width + height - 1 };
+
+    Diagonals {
+      width,
+      height,
+      kind,
+      front: 0,
+      back: count
+    }
+  }
+
+  fn line(&self, part: usize) -> DiagonalLine {
+    let anti_x = if part < self.width { part } else { self.width - 1 };
+    let start_y = part - anti_x;
+    let len = std::cmp::min(anti_x + 1, self.height - start_y);
+
+    let start_x = match self.kind {
+      DiagonalKind::Anti => anti_x,
+      DiagonalKind::Main => self.width - 1 - anti_x
+    };
+
+    DiagonalLine {
+      start_x,
+      start_y,
+      kind: self.kind,
+      reversed: false,
+      front: 0,
+      back: len
+    }
+  }
+}
+
+impl Iterator for Diagonals {
+  type Item = DiagonalLine;
+
+  fn next(&mut self) -> Option<DiagonalLine> {
+    if self.front >= self.back {
+      return None;
+    }
+
+    let line = self.line(self.front);
+    self.front += 1;
+
+    Some(line)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let len = self.back - self.front;
+    (len, Some(len))
+  }
+}
+
+impl DoubleEndedIterator for Diagonals {
+  fn next_back(&mut self) -> Option<DiagonalLine> {
+    if self.front >= self.back {
+      return None;
+    }
+
+    self.back -= 1;
+
+    Some(self.line(self.back))
+  }
+}
+
+impl ExactSizeIterator for Diagonals {}
+
+// the lines `oblique` produces
+pub fn anti_diagonals(width: usize, height: usize) -> Diagonals {
+  Diagonals::new(width, height, DiagonalKind::Anti)
+}
+
+pub fn diagonals(width: usize, height: usize) -> Diagonals {
+  Diagonals::new(width, height, DiagonalKind::Main)
+}
+
+// JPEG style: starts in the top left corner and alternates the direction
+// of every anti-diagonal
+pub fn zigzag(width: usize, height: usize) -> impl DoubleEndedIterator<Item = (usize, usize)> {
+  anti_diagonals(width, height)
+    .enumerate()
+    .flat_map(|(part, line)| if part.is_multiple_of(2) { line.reversed() } else { line })
+}
+
+#[derive(Clone, Debug)]
+struct Ring {
+  index: usize,
+  start: usize
+}
+
+// clockwise spiral starting in the top left corner, walking it backwards
+// spirals outward from the center and ends in the top left corner
+#[derive(Clone, Debug)]
+pub struct Spiral {
+  width: usize,
+  height: usize,
+  front: usize,
+  back: usize,
+  front_ring: Ring,
+  back_ring: Ring
+}
+
+impl Spiral {
+  fn new(width: usize, height: usize) -> Spiral {
+    let count = width * height;
+
+    let mut spiral = Spiral {
+      width,
+      height,
+      front: 0,
+      back: count,
+      front_ring: Ring { index: 0, start: 0 },
+      back_ring: Ring { index: 0, start: 0 }
+    };
+
+    if count > 0 {
+      while spiral.back_ring.start + spiral.ring_len(spiral.back_ring.index) < count {
+        spiral.back_ring.start += spiral.ring_len(spiral.back_ring.index);
+        spiral.back_ring.index += 1;
+      }
+    }
+
+    spiral
+  }
+
+  fn ring_size(&self, ring: usize) -> (usize, usize) {
+    (self.width - 2 * ring, self.height - 2 * ring)
+  }
+
+  fn ring_len(&self, ring: usize) -> usize {
+    let (w, h) = self.ring_size(ring);
+
+    if w == 1 || h == 1 {
+      w * h
+    } else {
+      2 * (w + h) - 4
+    }
+  }
+
+  fn coord_in_ring(&self, ring: usize, p: usize) -> (usize, usize) {
+    let (w, h) = self.ring_size(ring);
+    let k = ring;
+
+    if h == 1 {
+      return (k + p, k);
+    }
+
+    if w == 1 {
+      return (k, k + p);
+    }
+
+    if p < w {
+      (k + p, k)
+    } else if p < w + h - 1 {
+      (k + w - 1, k + p - (w - 1))
+    } else if p < 2 * w + h - 2 {
+      (k + w - 1 - (p - (w + h - 2)), k + h - 1)
+    } else {
+      (k, k + h - 1 - (p - (2 * w + h - 3)))
+    }
+  }
+}
+
+impl Iterator for Spiral {
+  type Item = (usize, usize);
+
+  fn next(&mut self) -> Option<(usize, usize)> {
+    if self.front >= self.back {
+      return None;
+    }
+
+    while self.front >= self.front_ring.start + self.ring_len(self.front_ring.index) {
+      self.front_ring.start += self.ring_len(self.front_ring.index);
+      self.front_ring.index += 1;
+    }
+
+    let coord = self.coord_in_ring(self.front_ring.index, self.front - self.front_ring.start);
+    self.front += 1;
+
+    Some(coord)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let len = self.back - self.front;
+    (len, Some(len))
+  }
+}
+
+impl DoubleEndedIterator for Spiral {
+  fn next_back(&mut self) -> Option<(usize, usize)> {
+    if self.front >= self.back {
+      return None;
+    }
+
+    self.back -= 1;
+
+    while self.back < self.back_ring.start {
+      self.back_ring.index -= 1;
+      self.back_ring.start -= self.ring_len(self.back_ring.index);
+    }
+
+    Some(self.coord_in_ring(self.back_ring.index, self.back - self.back_ring.start))
+  }
+}
+
+impl ExactSizeIterator for Spiral {}
+
+pub fn spiral_in(width: usize, height: usize) -> Spiral {
+  Spiral::new(width, height)
+}
+
+pub fn spiral_out(width: usize, height: usize) -> std::iter::Rev<Spiral> {
+  Spiral::new(width, height).rev()
+}
+
+// rows alternating left to right and right to left
+#[derive(Clone, Debug)]
+pub struct Boustrophedon {
+  width: usize,
+  front: usize,
+  back: usize
+}
+
+impl Boustrophedon {
+  fn coord_at(&self, index: usize) -> (usize, usize) {
+    let y = index / self.width;
+    let offset = index % self.width;
+
+    if y.is_multiple_of(2) {
+      (offset, y)
+    } else {
+      (self.width - 1 - offset, y)
+    }
+  }
+}
+
+impl Iterator for Boustrophedon {
+  type Item = (usize, usize);
+
+  fn next(&mut self) -> Option<(usize, usize)> {
+    if self.front >= self.back {
+      return None;
+    }
+
+    let coord = self.coord_at(self.front);
+    self.front += 1;
+
+    Some(coord)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let len = self.back - self.front;
+    (len, Some(len))
+  }
+}
+
+impl DoubleEndedIterator for Boustrophedon {
+  fn next_back(&mut self) -> Option<(usize, usize)> {
+    if self.front >= self.back {
+      return None;
+    }
+
+    self.back -= 1;
+
+    Some(self.coord_at(self.back))
+  }
+}
+
+impl ExactSizeIterator for Boustrophedon {}
+
+pub fn boustrophedon(width: usize, height: usize) -> Boustrophedon {
+  Boustrophedon {
+    width,
+    front: 0,
+    back: width * height
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use dp256::*;
+  use dp256::traversal::*;
+
+  fn collect_lines(lines: Diagonals) -> Vec<Vec<(usize, usize)>> {
+    lines.map(|line| line.collect()).collect()
+  }
+
+  fn assert_covers_grid<I>(width: usize, height: usize, coords: I)
+  where I: Iterator<Item = (usize, usize)> {
+    let mut seen: Flat2dArray<usize> = Flat2dArray::new_default(width, height);
+
+    for (x, y) in coords {
+      seen[(x, y)] += 1;
+    }
+
+    assert!(seen.iter().all(|&count| count == 1));
+  }
+
+  #[test]
+  fn test_anti_diagonals_match_oblique() {
+    for width in 1..8 {
+      for height in 1..8 {
+        let array = construct_regular_matrix(width, height);
+
+        let lazy: Vec<Vec<usize>> = anti_diagonals(width, height)
+          .map(|line| array.traverse(line).cloned().collect())
+          .collect();
+
+        assert_eq!(oblique(&array), lazy);
+      }
+    }
+  }
+
+  #[test]
+  fn test_diagonals() {
+    assert_eq!(
+      vec![
+        vec![(2, 0)],
+        vec![(1, 0), (2, 1)],
+        vec![(0, 0), (1, 1)],
+        vec![(0, 1)]
+      ],
+      collect_lines(diagonals(3, 2))
+    );
+  }
+
+  #[test]
+  fn test_diagonal_orders() {
+    let reversed: Vec<Vec<(usize, usize)>> = anti_diagonals(3, 2).rev().map(|line| line.rev().collect()).collect();
+
+    assert_eq!(
+      vec![
+        vec![(2, 1)],
+        vec![(1, 1), (2, 0)],
+        vec![(0, 1), (1, 0)],
+        vec![(0, 0)]
+      ],
+      reversed
+    );
+
+    let line = anti_diagonals(3, 3).nth(2).unwrap();
+
+    assert_eq!(vec![(0, 2), (1, 1), (2, 0)], line.clone().reversed().collect::<Vec<_>>());
+    assert_eq!(vec![(2, 0), (1, 1), (0, 2)], line.reversed().rev().collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn test_zigzag() {
+    let array = construct_regular_matrix(3, 3);
+
+    let values: Vec<usize> = array.traverse(zigzag(3, 3)).cloned().collect();
+
+    assert_eq!(vec![0, 1, 3, 6, 4, 2, 5, 7, 8], values);
+  }
+
+  #[test]
+  fn test_spiral() {
+    let array = construct_regular_matrix(4, 3);
+
+    let inward: Vec<usize> = array.traverse(spiral_in(4, 3)).cloned().collect();
+    let outward: Vec<usize> = array.traverse(spiral_out(4, 3)).cloned().collect();
+
+    assert_eq!(vec![0, 1, 2, 3, 7, 11, 10, 9, 8, 4, 5, 6], inward);
+    assert_eq!(vec![6, 5, 4, 8, 9, 10, 11, 7, 3, 2, 1, 0], outward);
+  }
+
+  #[test]
+  fn test_boustrophedon() {
+    let array = construct_regular_matrix(3, 3);
+
+    let values: Vec<usize> = array.traverse(boustrophedon(3, 3)).cloned().collect();
+
+    assert_eq!(vec![0, 1, 2, 5, 4, 3, 6, 7, 8], values);
+  }
+
+  #[test]
+  fn test_traversals_cover_grid() {
+    for width in 0..7 {
+      for height in 0..7 {
+        assert_covers_grid(width, height, anti_diagonals(width, height).flatten());
+        assert_covers_grid(width, height, diagonals(width, height).flat_map(|line| line.rev()));
+        assert_covers_grid(width, height, zigzag(width, height));
+        assert_covers_grid(width, height, zigzag(width, height).rev());
+        assert_covers_grid(width, height, spiral_in(width, height));
+        assert_covers_grid(width, height, spiral_out(width, height));
+        assert_covers_grid(width, height, boustrophedon(width, height).rev());
+
+        assert_eq!(width * height, spiral_in(width, height).len());
+      }
+    }
+  }
+
+  #[test]
+  fn test_write_traversal() {
+    let array = construct_regular_matrix(5, 4);
+
+    let mut result: Flat2dArray<usize> = Flat2dArray::new_default(5, 4);
+    let values: Vec<usize> = array.traverse(spiral_out(5, 4)).cloned().collect();
+    result.write_traversal(spiral_out(5, 4), values).unwrap();
+    assert_eq!(array, result);
+
+    let mut result: Flat2dArray<usize> = Flat2dArray::new_default(5, 4);
+    let values: Vec<usize> = array.traverse(zigzag(5, 4)).cloned().collect();
+    result.write_traversal(zigzag(5, 4), values).unwrap();
+    assert_eq!(array, result);
+
+    let mut result: Flat2dArray<usize> = Flat2dArray::new_default(5, 4);
+    let values: Vec<usize> = array.traverse(boustrophedon(5, 4)).cloned().collect();
+    result.write_traversal(boustrophedon(5, 4), values).unwrap();
+    assert_eq!(array, result);
+
+    let mut result: Flat2dArray<usize> = Flat2dArray::new_default(5, 4);
+    let values: Vec<usize> = array.traverse(diagonals(5, 4).flatten()).cloned().collect();
+    result.write_traversal(diagonals(5, 4).flatten(), values).unwrap();
+    assert_eq!(array, result);
+  }
+
+  #[test]
+  fn test_write_traversal_invalid() {
+    let mut array: Flat2dArray<usize> = Flat2dArray::new_default(2, 2);
+
+    assert!(array.write_traversal(spiral_in(2, 2), vec![1, 2, 3]).is_err());
+    assert!(array.write_traversal(spiral_in(2, 2), vec![1, 2, 3, 4, 5]).is_err());
+    assert!(array.write_traversal(spiral_in(3, 2), vec![1, 2, 3, 4, 5, 6]).is_err());
+    assert_eq!(Flat2dArray::new_default(2, 2), array);
+
+    assert!(array.write_traversal(spiral_in(2, 2), vec![1, 2, 3, 4]).is_ok());
+  }
+
+}
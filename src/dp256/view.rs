@@ -0,0 +1,472 @@
+// Borrowed views into a Flat2dArray.
+//
+// A view maps its own (x, y) to an index of the array's storage through an
+// offset and a step per axis. Sub-regions move the offset, strided views
+// multiply the steps and transposed views swap them, so none of them copy.
+
+use std::ops::{
+  Index,
+  IndexMut
+};
+
+use super::{
+  Flat2dArray,
+  Grid,
+  GridMut
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Layout {
+  offset: usize,
+  step_x: usize,
+  step_y: usize,
+  width: usize,
+  height: usize
+}
+
+impl Layout {
+  fn of<T>(array: &Flat2dArray<T>) -> Layout {
+    Layout {
+      offset: 0,
+      step_x: 1,
+      step_y: array.get_width(),
+      width: array.get_width(),
+      height: array.get_height()
+    }
+  }
+
+  fn index(&self, x: usize, y: usize) -> Option<usize> {
+    if x >= self.width || y >= self.height {
+      return None;
+    }
+
+    Some(self.offset + x * self.step_x + y * self.step_y)
+  }
+
+  fn sub(&self, x: usize, y: usize, width: usize, height: usize) -> Layout {
+    if x + width > self.width || y + height > self.height {
+      panic!("Out of bounds: {}x{} at {}, {}", width, height, x, y);
+    }
+
+    Layout {
+      offset: self.offset + x * self.step_x + y * self.step_y,
+      width,
+      height,
+      ..*self
+    }
+  }
+
+  fn strided(&self, nth_x: usize, nth_y: usize) -> Layout {
+    assert!(nth_x > 0 && nth_y > 0);
+
+    Layout {
+      step_x: self.step_x * nth_x,
+      step_y: self.step_y * nth_y,
+      width: self.width.div_ceil(nth_x),
+      height: self.height.div_ceil(nth_y),
+      ..*self
+    }
+  }
+
+  fn transposed(&self) -> Layout {
+    Layout {
+      offset: self.offset,
+      step_x: self.step_y,
+      step_y: self.step_x,
+      width: self.height,
+      height: self.width
+    }
+  }
+
+  fn coords(&self) -> impl Iterator<Item = (usize, usize)> {
+    let width = self.width;
+    (0 .. width * self.height).map(move |i| (i % width, i / width))
+  }
+}
+
+#[derive(Debug)]
+pub struct View<'a, T: 'a> {
+  data: &'a [T],
+  layout: Layout
+}
+
+impl<'a, T> Clone for View<'a, T> {
+  fn clone(&self) -> View<'a, T> {
+    *self
+  }
+}
+
+impl<'a, T> Copy for View<'a, T> {}
+
+impl<'a, T> View<'a, T> {
+  pub fn get_width(&self) -> usize {
+    self.layout.width
+  }
+
+  pub fn get_height(&self) -> usize {
+    self.layout.height
+  }
+
+  pub fn get_ref(&self, x: usize, y: usize) -> Option<&'a T> {
+    self.layout.index(x, y).map(|index| &self.data[index])
+  }
+
+  pub fn sub_view(&self, x: usize, y: usize, width: usize, height: usize) -> View<'a, T> {
+    View {
+      data: self.data,
+      layout: self.layout.sub(x, y, width, height)
+    }
+  }
+
+  pub fn strided(&self, nth_x: usize, nth_y: usize) -> View<'a, T> {
+    View {
+      data: self.data,
+      layout: self.layout.strided(nth_x, nth_y)
+    }
+  }
+
+  pub fn every_nth_row(&self, n: usize) -> View<'a, T> {
+    self.strided(1, n)
+  }
+
+  pub fn every_nth_column(&self, n: usize) -> View<'a, T> {
+    self.strided(n, 1)
+  }
+
+  pub fn transposed(&self) -> View<'a, T> {
+    View {
+      data: self.data,
+      layout: self.layout.transposed()
+    }
+  }
+
+  // row-major, like the iteration of a Flat2dArray
+  pub fn iter(&self) -> impl Iterator<Item = &'a T> {
+    let view = *self;
+    self.layout.coords().map(move |(x, y)| view.get_ref(x, y).unwrap())
+  }
+
+  pub fn iter_enumerated(&self) -> impl Iterator<Item = ((usize, usize), &'a T)> {
+    let view = *self;
+    self.layout.coords().map(move |(x, y)| ((x, y), view.get_ref(x, y).unwrap()))
+  }
+
+  pub fn row(&self, y: usize) -> impl Iterator<Item = &'a T> {
+    self.sub_view(0, y, self.get_width(), 1).iter()
+  }
+
+  pub fn column(&self, x: usize) -> impl Iterator<Item = &'a T> {
+    self.sub_view(x, 0, 1, self.get_height()).iter()
+  }
+
+  pub fn to_array(&self) -> Flat2dArray<T>
+  where T: Clone {
+    Flat2dArray::from_fn(self.get_width(), self.get_height(), |x, y| self[(x, y)].clone())
+  }
+}
+
+impl<'a, T> Index<(usize, usize)> for View<'a, T> {
+  type Output = T;
+
+  fn index(&self, (x, y): (usize, usize)) -> &T {
+    match self.get_ref(x, y) {
+      Some(value) => value,
+      None => panic!("Out of bounds: {}, {}", x, y)
+    }
+  }
+}
+
+impl<'a, T> Grid for View<'a, T> {
+  type Item = T;
+
+  fn get_width(&self) -> usize {
+    self.layout.width
+  }
+
+  fn get_height(&self) -> usize {
+    self.layout.height
+  }
+
+  fn get_ref(&self, x: usize, y: usize) -> Option<&T> {
+    View::get_ref(self, x, y)
+  }
+}
+
+#[derive(Debug)]
+pub struct ViewMut<'a, T: 'a> {
+  data: &'a mut [T],
+  layout: Layout
+}
+
+impl<'a, T> ViewMut<'a, T> {
+  pub fn get_width(&self) -> usize {
+    self.layout.width
+  }
+
+  pub fn get_height(&self) -> usize {
+    self.layout.height
+  }
+
+  pub fn get_ref(&self, x: usize, y: usize) -> Option<&T> {
+    self.layout.index(x, y).map(move |index| &self.data[index])
+  }
+
+  pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+    match self.layout.index(x, y) {
+      Some(index) => Some(&mut self.data[index]),
+      None => None
+    }
+  }
+
+  pub fn as_view(&self) -> View<'_, T> {
+    View {
+      data: self.data,
+      layout: self.layout
+    }
+  }
+
+  fn with_layout(&mut self, layout: Layout) -> ViewMut<'_, T> {
+    ViewMut {
+      data: self.data,
+      layout
+    }
+  }
+
+  pub fn sub_view_mut(&mut self, x: usize, y: usize, width: usize, height: usize) -> ViewMut<'_, T> {
+    let layout = self.layout.sub(x, y, width, height);
+    self.with_layout(layout)
+  }
+
+  pub fn strided_mut(&mut self, nth_x: usize, nth_y: usize) -> ViewMut<'_, T> {
+    let layout = self.layout.strided(nth_x, nth_y);
+    self.with_layout(layout)
+  }
+
+  pub fn every_nth_row_mut(&mut self, n: usize) -> ViewMut<'_, T> {
+    self.strided_mut(1, n)
+  }
+
+  pub fn every_nth_column_mut(&mut self, n: usize) -> ViewMut<'_, T> {
+    self.strided_mut(n, 1)
+  }
+
+  pub fn transposed_mut(&mut self) -> ViewMut<'_, T> {
+    let layout = self.layout.transposed();
+    self.with_layout(layout)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &T> {
+    self.as_view().iter()
+  }
+
+  pub fn iter_enumerated(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+    self.as_view().iter_enumerated()
+  }
+
+  // the steps of a view can overlap arbitrary parts of the storage, so
+  // mutable iteration hands out one element at a time
+  pub fn for_each_mut<F>(&mut self, mut f: F)
+  where F: FnMut((usize, usize), &mut T) {
+    for (x, y) in self.layout.coords() {
+      f((x, y), &mut self[(x, y)]);
+    }
+  }
+
+  pub fn fill(&mut self, value: T)
+  where T: Clone {
+    self.for_each_mut(|_, cell| *cell = value.clone());
+  }
+
+  pub fn copy_from<G>(&mut self, grid: &G) -> Result<(), String>
+  where G: Grid<Item = T>, T: Clone {
+    if grid.get_width() != self.get_width() || grid.get_height() != self.get_height() {
+      return Err(format!("expected a {}x{} grid, got {}x{}", self.get_width(), self.get_height(), grid.get_width(), grid.get_height()));
+    }
+
+    self.for_each_mut(|(x, y), cell| *cell = grid.get_ref(x, y).unwrap().clone());
+
+    Ok(())
+  }
+
+  pub fn to_array(&self) -> Flat2dArray<T>
+  where T: Clone {
+    self.as_view().to_array()
+  }
+}
+
+impl<'a, T> Index<(usize, usize)> for ViewMut<'a, T> {
+  type Output = T;
+
+  fn index(&self, (x, y): (usize, usize)) -> &T {
+    match self.get_ref(x, y) {
+      Some(value) => value,
+      None => panic!("Out of bounds: {}, {}", x, y)
+    }
+  }
+}
+
+impl<'a, T> IndexMut<(usize, usize)> for ViewMut<'a, T> {
+  fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+    match self.get_mut(x, y) {
+      Some(value) => value,
+      None => panic!("Out of bounds: {}, {}", x, y)
+    }
+  }
+}
+
+impl<'a, T> Grid for ViewMut<'a, T> {
+  type Item = T;
+
+  fn get_width(&self) -> usize {
+    self.layout.width
+  }
+
+  fn get_height(&self) -> usize {
+    self.layout.height
+  }
+
+  fn get_ref(&self, x: usize, y: usize) -> Option<&T> {
+    ViewMut::get_ref(self, x, y)
+  }
+}
+
+impl<'a, T> GridMut for ViewMut<'a, T> {
+  fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+    ViewMut::get_mut(self, x, y)
+  }
+}
+
+impl<T> Flat2dArray<T> {
+  pub fn view(&self) -> View<'_, T> {
+    View {
+      data: self.as_slice(),
+      layout: Layout::of(self)
+    }
+  }
+
+  pub fn view_mut(&mut self) -> ViewMut<'_, T> {
+    let layout = Layout::of(self);
+
+    ViewMut {
+      data: self.as_mut_slice(),
+      layout
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use dp256::*;
+  use dp256::traversal::*;
+
+  #[test]
+  fn test_sub_view() {
+    let array = construct_regular_matrix(5, 4);
+    let view = array.view().sub_view(1, 1, 3, 2);
+
+    assert_eq!(3, view.get_width());
+    assert_eq!(2, view.get_height());
+    assert_eq!(6, view[(0, 0)]);
+    assert_eq!(13, view[(2, 1)]);
+    assert_eq!(None, view.get_ref(3, 0));
+
+    assert_eq!(vec![6, 7, 8, 11, 12, 13], view.iter().cloned().collect::<Vec<usize>>());
+    assert_eq!(vec![12], view.sub_view(1, 1, 1, 1).iter().cloned().collect::<Vec<usize>>());
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_sub_view_out_of_bounds() {
+    let array = construct_regular_matrix(5, 4);
+
+    array.view().sub_view(3, 0, 3, 1);
+  }
+
+  #[test]
+  fn test_strided() {
+    let array = construct_regular_matrix(5, 4);
+
+    let rows = array.view().every_nth_row(2);
+    assert_eq!(vec![0, 1, 2, 3, 4, 10, 11, 12, 13, 14], rows.iter().cloned().collect::<Vec<usize>>());
+
+    let columns = array.view().every_nth_column(2);
+    assert_eq!(3, columns.get_width());
+    assert_eq!(vec![5, 7, 9], columns.row(1).cloned().collect::<Vec<usize>>());
+
+    let both = array.view().strided(3, 3);
+    assert_eq!(Flat2dArray::from(vec![vec![0, 3], vec![15, 18]]), both.to_array());
+  }
+
+  #[test]
+  fn test_transposed() {
+    let array = construct_regular_matrix(5, 4);
+    let view = array.view().transposed();
+
+    assert_eq!(array.transpose(), view.to_array());
+    assert_eq!(vec![2, 7, 12, 17], view.row(2).cloned().collect::<Vec<usize>>());
+    assert_eq!(oblique(&array.transpose()), oblique(&view));
+  }
+
+  #[test]
+  fn test_oblique_on_view() {
+    let array = construct_regular_matrix(6, 6);
+    let view = array.view().sub_view(2, 1, 3, 4);
+
+    assert_eq!(oblique(&view.to_array()), oblique(&view));
+    assert_eq!(view.to_array(), de_oblique(&oblique(&view), 3, 4).unwrap());
+  }
+
+  #[test]
+  fn test_view_mut() {
+    let mut array: Flat2dArray<usize> = Flat2dArray::new_default(4, 4);
+
+    {
+      let mut view = array.view_mut();
+      let mut tile = view.sub_view_mut(2, 0, 2, 2);
+
+      tile[(1, 1)] = 5;
+      tile.set(0, 1, 4);
+    }
+
+    assert_eq!(5, array[(3, 1)]);
+    assert_eq!(4, array[(2, 1)]);
+
+    array.view_mut().every_nth_column_mut(3).fill(9);
+
+    assert_eq!(vec![9, 0, 0, 9], array.row(2).to_vec());
+
+    array.view_mut().transposed_mut().sub_view_mut(0, 1, 4, 1).fill(1);
+
+    assert_eq!(vec![1, 1, 1, 1], array.column(1).cloned().collect::<Vec<usize>>());
+  }
+
+  #[test]
+  fn test_tiles_in_place() {
+    let mut array = construct_regular_matrix(6, 4);
+    let original = array.clone();
+
+    for tile_y in 0..2 {
+      for tile_x in 0..3 {
+        let mut view = array.view_mut();
+        let mut tile = view.sub_view_mut(tile_x * 2, tile_y * 2, 2, 2);
+
+        let values: Vec<usize> = tile.traverse(spiral_in(2, 2)).map(|v| v * 10).collect();
+        tile.write_traversal(spiral_in(2, 2), values).unwrap();
+      }
+    }
+
+    assert_eq!(original.map(|v| v * 10), array);
+  }
+
+  #[test]
+  fn test_copy_from() {
+    let mut array: Flat2dArray<usize> = Flat2dArray::new_default(4, 4);
+    let source = construct_regular_matrix(2, 2);
+
+    array.view_mut().sub_view_mut(1, 1, 2, 2).copy_from(&source).unwrap();
+
+    assert_eq!(vec![0, 2, 3, 0], array.row(2).to_vec());
+    assert!(array.view_mut().copy_from(&source).is_err());
+  }
+
+}
@@ -0,0 +1,145 @@
+// calendar.rs <weldale@gmail.com>
+//
+// Old Style (Julian) and New Style (Gregorian) dates. All dates are kept in
+// the proleptic Gregorian calendar, Julian dates are converted through their
+// Julian day number.
+
+use chrono::{
+  Datelike,
+  NaiveDate
+};
+
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Calendar {
+  // Old Style
+  Julian,
+  // New Style
+  Gregorian
+}
+
+impl Calendar {
+  // the Gregorian date of the given day in this calendar, None if there's no such day
+  pub fn normalize(self, year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    match self {
+      Calendar::Julian => from_julian(year, month, day),
+      Calendar::Gregorian => NaiveDate::from_ymd_opt(year, month, day)
+    }
+  }
+
+  // the (year, month, day) this calendar gives a Gregorian date
+  pub fn ymd(self, date: NaiveDate) -> (i32, u32, u32) {
+    match self {
+      Calendar::Julian => to_julian(date),
+      Calendar::Gregorian => (date.year(), date.month(), date.day())
+    }
+  }
+}
+
+impl fmt::Display for Calendar {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Calendar::Julian => write!(f, "O.S."),
+      Calendar::Gregorian => write!(f, "N.S.")
+    }
+  }
+}
+
+// the Julian day number of 0001-01-01 is one more than this
+const CE_OFFSET: i32 = 1721425;
+
+pub fn is_julian_leap_year(year: i32) -> bool {
+  year.rem_euclid(4) == 0
+}
+
+pub fn julian_days_in_month(year: i32, month: u32) -> u32 {
+  match month {
+    2 if is_julian_leap_year(year) => 29,
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    _ => 31
+  }
+}
+
+fn julian_day_number(year: i32, month: u32, day: u32) -> i32 {
+  let a = (14 - month as i32) / 12;
+  let y = year + 4800 - a;
+  let m = month as i32 + 12 * a - 3;
+
+  day as i32 + (153 * m + 2) / 5 + 365 * y + y.div_euclid(4) - 32083
+}
+
+pub fn from_julian(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+  if month == 0 || month > 12 || day == 0 || day > julian_days_in_month(year, month) {
+    return None;
+  }
+
+  NaiveDate::from_num_days_from_ce_opt(julian_day_number(year, month, day) - CE_OFFSET)
+}
+
+pub fn to_julian(date: NaiveDate) -> (i32, u32, u32) {
+  let c = date.num_days_from_ce() + CE_OFFSET + 32082;
+  let d = (4 * c + 3).div_euclid(1461);
+  let e = c - (1461 * d).div_euclid(4);
+  let m = (5 * e + 2) / 153;
+
+  let day = e - (153 * m + 2) / 5 + 1;
+  let month = m + 3 - 12 * (m / 10);
+  let year = d - 4800 + m / 10;
+
+  (year, month as u32, day as u32)
+}
+
+// the first New Style day in Britain and its colonies, the day after
+// Wednesday, 2 September 1752 O.S.
+pub fn british_switch() -> NaiveDate {
+  NaiveDate::from_ymd(1752, 9, 14)
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd(y, m, d)
+  }
+
+  #[test]
+  fn test_from_julian() {
+    // Washington's birthday, Feb 11 1731/32 O.S.
+    assert_eq!(Some(ymd(1732, 2, 22)), from_julian(1732, 2, 11));
+    assert_eq!(Some(ymd(1752, 9, 14)), from_julian(1752, 9, 3));
+    assert_eq!(Some(ymd(1582, 10, 15)), from_julian(1582, 10, 5));
+    assert_eq!(Some(ymd(1700, 3, 11)), from_julian(1700, 2, 29));
+    assert_eq!(Some(ymd(1, 1, 1)), from_julian(1, 1, 3));
+
+    assert_eq!(None, from_julian(1701, 2, 29));
+    assert_eq!(None, from_julian(1700, 13, 1));
+    assert_eq!(None, from_julian(1700, 4, 31));
+  }
+
+  #[test]
+  fn test_round_trip() {
+    assert_eq!((1732, 2, 11), to_julian(ymd(1732, 2, 22)));
+    assert_eq!((1700, 2, 29), to_julian(ymd(1700, 3, 11)));
+
+    let mut date = ymd(1500, 1, 1);
+
+    while date < ymd(1900, 1, 1) {
+      let (y, m, d) = Calendar::Julian.ymd(date);
+      assert_eq!(Some(date), Calendar::Julian.normalize(y, m, d));
+      date = date.succ();
+    }
+  }
+
+  #[test]
+  fn test_calendar() {
+    assert_eq!(Some(ymd(1732, 2, 22)), Calendar::Gregorian.normalize(1732, 2, 22));
+    assert_eq!((1732, 2, 22), Calendar::Gregorian.ymd(ymd(1732, 2, 22)));
+    assert_eq!(british_switch(), from_julian(1752, 9, 2).unwrap().succ());
+    assert_eq!("O.S.", Calendar::Julian.to_string());
+  }
+
+}
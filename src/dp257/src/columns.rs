@@ -0,0 +1,608 @@
+// columns.rs <weldale@gmail.com>
+//
+// Reads files whose columns don't follow the layout of presidents.csv. Every
+// field is looked up by header name or by position into a generic `Record`
+// described by a `Schema`.
+
+use chrono::{
+  DateTime,
+  NaiveDate,
+  TimeZone,
+  UTC
+};
+
+use csv;
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::Read;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use calendar::Calendar;
+use date::{
+  DateParser,
+  NumericOrder,
+  ParsedDate,
+  Precision
+};
+use {
+  LineBreaks,
+  LineCounter,
+  Lifespan,
+  RowError
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Column {
+  // zero based
+  Index(usize),
+  // compared case insensitive and without surrounding whitespace
+  Header(String)
+}
+
+impl FromStr for Column {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Column, String> {
+    let s = s.trim();
+
+    if s.is_empty() {
+      return Err("empty column name".to_string());
+    }
+
+    Ok(match s.parse::<usize>() {
+      Ok(index) => Column::Index(index),
+      Err(_) => Column::Header(s.to_string())
+    })
+  }
+}
+
+impl Column {
+  pub fn resolve(&self, headers: &[String]) -> Result<usize, String> {
+    match *self {
+      Column::Index(index) if index < headers.len() => Ok(index),
+      Column::Index(index) => Err(format!("column {} doesn't exist, the file has {} columns", index, headers.len())),
+      Column::Header(ref name) => headers.iter()
+        .position(|header| header.trim().eq_ignore_ascii_case(name))
+        .ok_or(format!("no column named \"{}\"", name))
+    }
+  }
+}
+
+fn field(row: &[String], index: usize) -> Result<String, String> {
+  row.get(index).cloned().ok_or(format!("row has {} fields, column {} is missing", row.len(), index))
+}
+
+// how the text of a date column is read
+#[derive(Clone, Debug)]
+pub enum DateFormat {
+  // anything `DateParser` understands
+  Lenient(DateParser),
+  // a strftime pattern naming a full date, like %d.%m.%Y
+  Pattern(String)
+}
+
+impl DateFormat {
+  pub fn parse(&self, s: &str) -> Result<Option<ParsedDate>, String> {
+    match *self {
+      DateFormat::Lenient(ref parser) => parser.parse(s),
+      DateFormat::Pattern(ref pattern) => {
+        let s = s.trim();
+
+        if s.is_empty() {
+          return Ok(None);
+        }
+
+        NaiveDate::parse_from_str(s, pattern)
+          .map(|date| Some(ParsedDate {
+            date: UTC.from_utc_datetime(&date.and_hms(0, 0, 0)),
+            precision: Precision::Day,
+            calendar: Calendar::Gregorian
+          }))
+          .map_err(|e| format!("{} in \"{}\", expected {}", e, s, pattern))
+      }
+    }
+  }
+}
+
+impl Default for DateFormat {
+  fn default() -> DateFormat {
+    DateFormat::Lenient(DateParser::new())
+  }
+}
+
+// "month-first", "day-first" or a pattern
+impl FromStr for DateFormat {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<DateFormat, String> {
+    match s.trim() {
+      "month-first" => Ok(DateFormat::Lenient(DateParser::new().numeric_order(NumericOrder::MonthFirst))),
+      "day-first" => Ok(DateFormat::Lenient(DateParser::new().numeric_order(NumericOrder::DayFirst))),
+      pattern if pattern.contains('%') => Ok(DateFormat::Pattern(pattern.to_string())),
+      other => Err(format!("unknown date format: {}", other))
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct DateColumn {
+  pub column: Column,
+  pub format: DateFormat
+}
+
+impl DateColumn {
+  pub fn new(column: Column) -> DateColumn {
+    DateColumn {
+      column,
+      format: DateFormat::default()
+    }
+  }
+
+  pub fn format(mut self, format: DateFormat) -> DateColumn {
+    self.format = format;
+    self
+  }
+}
+
+// "born" or "born | %d.%m.%Y"
+impl FromStr for DateColumn {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<DateColumn, String> {
+    let mut parts = s.splitn(2, '|');
+    let column = DateColumn::new(parts.next().unwrap_or("").parse()?);
+
+    match parts.next() {
+      Some(format) => Ok(column.format(format.parse()?)),
+      None => Ok(column)
+    }
+  }
+}
+
+// a lifespan read from any table, with the columns it wasn't built from kept
+// as named attributes
+#[derive(Clone, Debug)]
+pub struct Record {
+  label: String,
+  start: ParsedDate,
+  end: Option<ParsedDate>,
+  attributes: Vec<(String, String)>
+}
+
+impl Record {
+  pub fn get_label(&self) -> &str {
+    &self.label
+  }
+
+  pub fn get_start(&self) -> ParsedDate {
+    self.start
+  }
+
+  pub fn get_end(&self) -> Option<ParsedDate> {
+    self.end
+  }
+
+  // None if the schema has no such attribute
+  pub fn get_attribute(&self, name: &str) -> Option<&str> {
+    self.attributes.iter()
+      .find(|(key, _)| key == name)
+      .map(|(_, value)| value.as_str())
+  }
+
+  // in the order of the schema
+  pub fn get_attributes(&self) -> &[(String, String)] {
+    &self.attributes
+  }
+}
+
+impl Lifespan for Record {
+  fn start(&self) -> DateTime<UTC> {
+    self.start.date
+  }
+
+  fn end(&self) -> Option<DateTime<UTC>> {
+    self.end.map(|date| date.date)
+  }
+}
+
+// which columns make up a `Record`
+#[derive(Clone, Debug)]
+pub struct Schema {
+  label: Column,
+  start: DateColumn,
+  end: Option<DateColumn>,
+  attributes: Vec<(String, Column)>,
+  delimiter: u8
+}
+
+impl Schema {
+  pub fn new(label: Column, start: DateColumn) -> Schema {
+    Schema {
+      label,
+      start,
+      end: None,
+      attributes: Vec::new(),
+      delimiter: b','
+    }
+  }
+
+  pub fn end(mut self, end: DateColumn) -> Schema {
+    self.end = Some(end);
+    self
+  }
+
+  pub fn attribute(mut self, name: &str, column: Column) -> Schema {
+    self.attributes.push((name.to_string(), column));
+    self
+  }
+
+  pub fn delimiter(mut self, delimiter: u8) -> Schema {
+    self.delimiter = delimiter;
+    self
+  }
+
+  // the layout of presidents.csv, the places are kept as the attributes
+  // "birth place" and "death place"
+  pub fn presidents(parser: DateParser) -> Schema {
+    let date = |index| DateColumn::new(Column::Index(index)).format(DateFormat::Lenient(parser));
+
+    Schema::new(Column::Index(0), date(1))
+      .end(date(3))
+      .attribute("birth place", Column::Index(2))
+      .attribute("death place", Column::Index(4))
+  }
+
+  pub fn get_attribute_names(&self) -> Vec<&str> {
+    self.attributes.iter().map(|(name, _)| name.as_str()).collect()
+  }
+}
+
+// a single character, or \t for tabs
+pub fn parse_delimiter(s: &str) -> Result<u8, String> {
+  match s.as_bytes() {
+    &[delimiter] => Ok(delimiter),
+    _ if s == "\\t" => Ok(b'\t'),
+    _ => Err(format!("the delimiter must be a single character, got {}", s))
+  }
+}
+
+// one setting per line, blank lines and lines starting with # are skipped:
+//
+//   delimiter = ;
+//   label = full name
+//   start = born | %d.%m.%Y
+//   end = died | day-first
+//   attribute state = birth state
+impl FromStr for Schema {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Schema, String> {
+    let mut label = None;
+    let mut start = None;
+    let mut end = None;
+    let mut attributes = Vec::new();
+    let mut delimiter = b',';
+
+    for (index, line) in s.lines().enumerate() {
+      let line = line.trim();
+
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      let error = |message: String| format!("schema line {}: {}", index + 1, message);
+
+      let (key, value) = match line.find('=') {
+        Some(pos) => (line[..pos].trim(), &line[pos + 1..]),
+        None => return Err(error(format!("expected key = value, got {}", line)))
+      };
+
+      match key {
+        "label" => label = Some(value.parse().map_err(&error)?),
+        "start" => start = Some(value.parse().map_err(&error)?),
+        "end" => end = Some(value.parse().map_err(&error)?),
+        "delimiter" => delimiter = parse_delimiter(value.trim()).map_err(&error)?,
+        _ if key.starts_with("attribute ") => {
+          attributes.push((key["attribute ".len()..].trim().to_string(), value.parse().map_err(&error)?));
+        },
+        _ => return Err(error(format!("unknown setting: {}", key)))
+      }
+    }
+
+    Ok(Schema {
+      label: label.ok_or("the schema needs a label column")?,
+      start: start.ok_or("the schema needs a start column")?,
+      end,
+      attributes,
+      delimiter
+    })
+  }
+}
+
+// the records that could be read, and why the others couldn't
+#[derive(Debug)]
+pub struct RecordReport {
+  pub records: Vec<Record>,
+  pub errors: Vec<RowError>
+}
+
+struct ResolvedSchema<'a> {
+  schema: &'a Schema,
+  label: usize,
+  start: usize,
+  end: Option<usize>,
+  attributes: Vec<usize>
+}
+
+impl<'a> ResolvedSchema<'a> {
+  fn new(schema: &'a Schema, headers: &[String]) -> Result<ResolvedSchema<'a>, String> {
+    let mut attributes = Vec::new();
+
+    for (_, column) in &schema.attributes {
+      attributes.push(column.resolve(headers)?);
+    }
+
+    Ok(ResolvedSchema {
+      schema,
+      label: schema.label.resolve(headers)?,
+      start: schema.start.column.resolve(headers)?,
+      end: match schema.end {
+        Some(ref end) => Some(end.column.resolve(headers)?),
+        None => None
+      },
+      attributes
+    })
+  }
+
+  fn parse(&self, row: &[String]) -> Result<Record, String> {
+    let start_field = field(row, self.start)?;
+    let start = self.schema.start.format.parse(&start_field)?.ok_or("missing start date")?;
+
+    let end = match (&self.schema.end, self.end) {
+      (Some(end), Some(index)) => {
+        let end_field = field(row, index)?;
+        let end = end.format.parse(&end_field)?;
+
+        if end.is_some_and(|end| end.date < start.date) {
+          return Err(format!("end date {} is before start date {}", end_field.trim(), start_field.trim()));
+        }
+
+        end
+      },
+      _ => None
+    };
+
+    let mut attributes = Vec::new();
+
+    for ((name, _), &index) in self.schema.attributes.iter().zip(&self.attributes) {
+      attributes.push((name.clone(), field(row, index)?.trim().to_string()));
+    }
+
+    Ok(Record {
+      label: field(row, self.label)?.trim().to_string(),
+      start,
+      end,
+      attributes
+    })
+  }
+}
+
+// reads one row at a time, for files too big to hold at once
+pub struct RecordStream<'a, R: Read> {
+  rdr: csv::Reader<LineCounter<R>>,
+  breaks: Rc<RefCell<LineBreaks>>,
+  resolved: ResolvedSchema<'a>
+}
+
+impl<'a, R: Read> RecordStream<'a, R> {
+  fn new(input: R, schema: &'a Schema) -> Result<RecordStream<'a, R>, String> {
+    let breaks = Rc::new(RefCell::new(LineBreaks::default()));
+    let counter = LineCounter { inner: input, breaks: breaks.clone() };
+
+    let mut rdr = csv::Reader::from_reader(counter).has_headers(true).flexible(true).delimiter(schema.delimiter);
+
+    let headers = rdr.headers().map_err(|e| e.to_string())?;
+    let resolved = ResolvedSchema::new(schema, &headers)?;
+
+    Ok(RecordStream {
+      rdr,
+      breaks,
+      resolved
+    })
+  }
+}
+
+impl<'a, R: Read> Iterator for RecordStream<'a, R> {
+  type Item = Result<Record, RowError>;
+
+  fn next(&mut self) -> Option<Result<Record, RowError>> {
+    let offset = self.rdr.byte_offset();
+    let row = self.rdr.records().next()?;
+
+    let line = self.breaks.borrow_mut().line_at(offset);
+
+    Some(row
+      .map_err(|e| e.to_string())
+      .and_then(|row| self.resolved.parse(&row))
+      .map_err(|message| RowError { line, message }))
+  }
+}
+
+fn read_schema<R: Read>(input: R, schema: &Schema) -> Result<RecordReport, String> {
+  let mut report = RecordReport {
+    records: Vec::new(),
+    errors: Vec::new()
+  };
+
+  for row in RecordStream::new(input, schema)? {
+    match row {
+      Ok(record) => report.records.push(record),
+      Err(error) => report.errors.push(error)
+    }
+  }
+
+  Ok(report)
+}
+
+// fails as a whole if the file can't be opened or a column of the schema doesn't exist
+pub fn stream_csv_schema<'a>(file_path: &str, schema: &'a Schema) -> Result<RecordStream<'a, File>, String> {
+  let file = File::open(file_path).map_err(|e| e.to_string())?;
+
+  RecordStream::new(file, schema)
+}
+
+// fails as a whole if the file can't be opened or a column of the schema doesn't exist
+pub fn read_csv_schema(file_path: &str, schema: &Schema) -> Result<RecordReport, String> {
+  let file = File::open(file_path).map_err(|e| e.to_string())?;
+
+  read_schema(file, schema)
+}
+
+pub fn read_csv_str_schema(data: &str, schema: &Schema) -> Result<RecordReport, String> {
+  read_schema(data.as_bytes(), schema)
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  use date::NumericOrder;
+
+  const DATA: &str = "id;died;full name;born\n\
+                      1;;Ada;10.12.1815\n\
+                      2;24.12.1890;Bob;01.02.1800\n\
+                      3;;Carl\n";
+
+  fn schema() -> Schema {
+    let day_first = DateFormat::Lenient(DateParser::new().numeric_order(NumericOrder::DayFirst));
+
+    Schema::new("Full Name".parse().unwrap(), DateColumn::new("born".parse().unwrap()).format(day_first.clone()))
+      .end(DateColumn::new(Column::Index(1)).format(day_first))
+      .delimiter(b';')
+  }
+
+  #[test]
+  fn test_columns() {
+    let report = read_csv_str_schema(DATA, &schema()).unwrap();
+
+    assert_eq!(2, report.records.len());
+    assert_eq!("Ada", report.records[0].get_label());
+    assert_eq!("1815-12-10", report.records[0].get_start().to_string());
+    assert_eq!("1890-12-24", report.records[1].get_end().unwrap().to_string());
+
+    assert_eq!(1, report.errors.len());
+    assert_eq!(4, report.errors[0].line);
+  }
+
+  #[test]
+  fn test_missing_column() {
+    let birthday = Schema::new(Column::Index(2), DateColumn::new(Column::Header("birthday".to_string()))).delimiter(b';');
+    assert!(read_csv_str_schema(DATA, &birthday).unwrap_err().contains("birthday"));
+
+    let out_of_range = Schema::new(Column::Index(2), DateColumn::new(Column::Index(9))).delimiter(b';');
+    assert!(read_csv_str_schema(DATA, &out_of_range).is_err());
+  }
+
+  #[test]
+  fn test_lines() {
+    // quoted line breaks, CRLF and blank lines
+    let data = "name;born\r\n\
+                \"Ada\r\nLovelace\";10.12.1815\r\n\
+                \r\n\
+                Bob;x\r\n\
+                \"Carl\n\n\";y\n\
+                \n\
+                \n\
+                Dora;z";
+
+    let schema = Schema::new(Column::Index(0), DateColumn::new(Column::Index(1))).delimiter(b';');
+    let report = read_csv_str_schema(data, &schema).unwrap();
+
+    assert_eq!("Ada\r\nLovelace", report.records[0].get_label());
+
+    let lines: Vec<usize> = report.errors.iter().map(|e| e.line).collect();
+    assert_eq!(vec![5, 6, 11], lines);
+  }
+
+  #[test]
+  fn test_presidents_matches_read_csv() {
+    let read = read_csv_schema("data/presidents.csv", &Schema::presidents(DateParser::new())).unwrap();
+    let decoded = ::read_csv("data/presidents.csv").unwrap();
+
+    assert!(read.errors.is_empty());
+    assert_eq!(decoded.presidents.len(), read.records.len());
+
+    for (a, b) in decoded.presidents.iter().zip(read.records.iter()) {
+      assert_eq!(a.get_name(), b.get_label());
+      assert_eq!(a.get_death_date(), b.get_end());
+      assert_eq!(Some(a.get_birth_place()), b.get_attribute("birth place"));
+      assert_eq!(a.get_death_place().unwrap_or(""), b.get_attribute("death place").unwrap());
+    }
+  }
+
+  const SCHEMA: &str = "# leases.csv\n\
+                        delimiter = ;\n\
+                        label = tenant\n\
+                        start = from | %d/%m/%Y\n\
+                        end = 2 | day-first\n\
+                        attribute flat = Flat No\n";
+
+  #[test]
+  fn test_schema() {
+    let schema: Schema = SCHEMA.parse().unwrap();
+    assert_eq!(vec!["flat"], schema.get_attribute_names());
+
+    let data = "tenant;from;until;flat no\n\
+                Ada;03/04/2010;05/06/2012;4b\n\
+                Bob;13/04/2012;;7\n\
+                Carl;April 2012;;1\n\
+                Dora;01/01/2013;1.1.2014\n\
+                Emil;02/02/2014;01/01/2014;9\n";
+
+    let report = read_csv_str_schema(data, &schema).unwrap();
+
+    assert_eq!(2, report.records.len());
+
+    let ada = &report.records[0];
+    assert_eq!("Ada", ada.get_label());
+    assert_eq!("2010-04-03", ada.get_start().to_string());
+    assert_eq!("2012-06-05", ada.get_end().unwrap().to_string());
+    assert_eq!(Some("4b"), ada.get_attribute("flat"));
+    assert_eq!(None, ada.get_attribute("floor"));
+    assert_eq!(None, report.records[1].end());
+
+    let lines: Vec<usize> = report.errors.iter().map(|e| e.line).collect();
+    assert_eq!(vec![4, 5, 6], lines);
+    assert!(report.errors[0].message.contains("%d/%m/%Y"));
+    assert_eq!("end date 01/01/2014 is before start date 02/02/2014", report.errors[2].message);
+  }
+
+  #[test]
+  fn test_schema_builder() {
+    let schema = Schema::new(Column::Index(0), DateColumn::new(Column::Index(1)))
+      .end(DateColumn::new(Column::Index(3)))
+      .attribute("state", Column::Header("birth place".to_string()));
+
+    let report = read_csv_schema("data/presidents.csv", &schema).unwrap();
+
+    assert!(report.errors.is_empty());
+    assert_eq!("George Washington", report.records[0].get_label());
+    assert_eq!(Some("Westmoreland Co. Va."), report.records[0].get_attribute("state"));
+
+    let missing = schema.clone().attribute("party", Column::Header("party".to_string()));
+    assert!(read_csv_schema("data/presidents.csv", &missing).is_err());
+  }
+
+  #[test]
+  fn test_invalid_schema() {
+    assert!("label = name".parse::<Schema>().is_err());
+    assert!("label = name\nstart = born\ncolour = red".parse::<Schema>().is_err());
+    assert!("label = name\nstart = born | dd.mm.yyyy".parse::<Schema>().is_err());
+    assert!("label\nstart = born".parse::<Schema>().unwrap_err().starts_with("schema line 1"));
+    assert!("label = name\nstart = born".parse::<Schema>().is_ok());
+  }
+
+}
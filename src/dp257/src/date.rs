@@ -0,0 +1,493 @@
+// date.rs <weldale@gmail.com>
+//
+// Lenient date parsing for hand written tables. Partial dates are resolved
+// to the first day of the period they name, `Precision` tells how much of
+// the date was actually given. Old Style dates are converted to New Style,
+// `calendar` tells which one the source used.
+
+use chrono::{
+  DateTime,
+  Datelike,
+  NaiveDate,
+  TimeZone,
+  UTC
+};
+
+use std::fmt;
+
+use calendar::Calendar;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Precision {
+  Year,
+  Month,
+  Day
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParsedDate {
+  // always New Style
+  pub date: DateTime<UTC>,
+  pub precision: Precision,
+  pub calendar: Calendar
+}
+
+impl fmt::Display for ParsedDate {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.precision {
+      Precision::Year => write!(f, "{}", self.date.format("%Y")),
+      Precision::Month => write!(f, "{}", self.date.format("%Y-%m")),
+      Precision::Day => write!(f, "{}", self.date.format("%Y-%m-%d"))
+    }
+  }
+}
+
+// how to read all-numeric dates like 04/07/1826
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NumericOrder {
+  MonthFirst,
+  DayFirst
+}
+
+const MONTHS: [&str; 12] = [
+  "january", "february", "march", "april", "may", "june",
+  "july", "august", "september", "october", "november", "december"
+];
+
+fn month_from_name(name: &str) -> Option<u32> {
+  let name = name.trim_end_matches('.').to_lowercase();
+
+  if name.len() < 3 {
+    return None;
+  }
+
+  // "Sept" is the only common abbreviation that isn't three letters long
+  MONTHS.iter()
+    .position(|month| *month == name || (name.len() <= 4 && month.starts_with(&name)))
+    .map(|index| index as u32 + 1)
+}
+
+// a date as written, before it's checked against its calendar
+struct Literal {
+  year: i32,
+  month: Option<u32>,
+  day: Option<u32>,
+  // given by dual dating
+  calendar: Option<Calendar>,
+  // the New Style day of a dual date like Feb 11/22
+  new_style_day: Option<u32>
+}
+
+fn parse_number(s: &str) -> Result<u32, String> {
+  s.parse::<u32>().map_err(|_| format!("not a number: {}", s))
+}
+
+fn parse_year(s: &str) -> Result<i32, String> {
+  if s.len() != 4 || !s.chars().all(|c| c.is_ascii_digit()) {
+    return Err(format!("expected a four digit year, got {}", s));
+  }
+
+  Ok(parse_number(s)? as i32)
+}
+
+fn from_parts(year: i32, month: Option<u32>, day: Option<u32>) -> Literal {
+  Literal {
+    year,
+    month,
+    day,
+    calendar: None,
+    new_style_day: None
+  }
+}
+
+// 1826, 1826-07, 1826-07-04
+fn parse_iso(s: &str) -> Result<Literal, String> {
+  let parts: Vec<&str> = s.split('-').collect();
+
+  if parts.len() > 3 {
+    return Err(format!("too many parts in date: {}", s));
+  }
+
+  let year = parse_year(parts[0])?;
+  let month = match parts.get(1) {
+    Some(part) => Some(parse_number(part)?),
+    None => None
+  };
+  let day = match parts.get(2) {
+    Some(part) => Some(parse_number(part)?),
+    None => None
+  };
+
+  Ok(from_parts(year, month, day))
+}
+
+// 7/4/1826, 4.7.1826, 07/1826
+fn parse_numeric(s: &str, separator: char, order: NumericOrder) -> Result<Literal, String> {
+  let parts: Vec<&str> = s.split(separator).collect();
+
+  match parts.len() {
+    2 => Ok(from_parts(parse_year(parts[1])?, Some(parse_number(parts[0])?), None)),
+    3 => {
+      let year = parse_year(parts[2])?;
+      let first = parse_number(parts[0])?;
+      let second = parse_number(parts[1])?;
+
+      // a first number that can't be a month settles the order by itself
+      let (month, day) = match order {
+        NumericOrder::MonthFirst if first <= 12 => (first, second),
+        NumericOrder::DayFirst if second > 12 && first <= 12 => (first, second),
+        _ => (second, first)
+      };
+
+      Ok(from_parts(year, Some(month), Some(day)))
+    },
+    _ => Err(format!("unrecognized date: {}", s))
+  }
+}
+
+fn is_number(s: &str) -> bool {
+  !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+}
+
+// 1731/32 is the year from Jan 1 to Mar 24 of 1732, which began on Mar 25 in
+// the Old Style reckoning
+fn dual_year(old: &str, new: &str) -> Result<String, String> {
+  let year = parse_year(old)?;
+  let next = (year + 1).to_string();
+
+  if new.len() > 4 || !next.ends_with(new) {
+    return Err(format!("{}/{} isn't a dual year", old, new));
+  }
+
+  Ok(next)
+}
+
+// Jul 4 1826, July 4, 1826, 4 July 1826, July 1826, and dual dates like
+// Feb 11/22 1731/32 or Feb 11 1731/32
+fn parse_named(s: &str) -> Result<Literal, String> {
+  let tokens: Vec<&str> = s.split(|c: char| c.is_whitespace() || c == ',')
+    .filter(|token| !token.is_empty())
+    .collect();
+
+  let mut month = None;
+  let mut numbers = Vec::new();
+  let mut dual = false;
+  let mut dual_year_token = None;
+  let mut new_style_day = None;
+
+  for token in tokens {
+    // ordinal days like 4th
+    let digits = token.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let is_ordinal = !digits.is_empty() && ["st", "nd", "rd", "th"].contains(&&token[digits.len()..]);
+
+    if let Some(pos) = token.find('/') {
+      let (old, new) = (&token[..pos], &token[pos + 1..]);
+
+      if !is_number(old) || !is_number(new) {
+        return Err(format!("unexpected text in date: {}", token));
+      }
+
+      if old.len() == 4 {
+        numbers.push(dual_year(old, new)?);
+        dual_year_token = Some(token);
+      } else {
+        numbers.push(old.to_string());
+        new_style_day = Some(parse_number(new)?);
+      }
+
+      dual = true;
+    } else if is_number(token) || (is_ordinal && is_number(digits)) {
+      numbers.push(digits.to_string());
+    } else if month.is_none() {
+      month = Some(month_from_name(token).ok_or(format!("unknown month: {}", token))?);
+    } else {
+      return Err(format!("unexpected text in date: {}", token));
+    }
+  }
+
+  let month = month.ok_or(format!("unrecognized date: {}", s))?;
+
+  let mut literal = match numbers.len() {
+    1 => from_parts(parse_year(&numbers[0])?, Some(month), None),
+    2 => {
+      let (year, day) = if numbers[0].len() == 4 { (&numbers[0], &numbers[1]) } else { (&numbers[1], &numbers[0]) };
+      from_parts(parse_year(year)?, Some(month), Some(parse_number(day)?))
+    },
+    _ => return Err(format!("unrecognized date: {}", s))
+  };
+
+  // the Old Style day decides, a month alone only has to overlap
+  if let Some(token) = dual_year_token {
+    let in_range = match literal.day {
+      Some(day) => month < 3 || (month == 3 && day <= 24),
+      None => month <= 3
+    };
+
+    if !in_range {
+      return Err(format!("dual year {} only applies from Jan 1 to Mar 24", token));
+    }
+  }
+
+  if dual {
+    literal.calendar = Some(Calendar::Julian);
+    literal.new_style_day = new_style_day;
+  }
+
+  Ok(literal)
+}
+
+// takes out an O.S. or N.S. mark, with or without dots or parentheses
+fn strip_calendar_mark(s: &str) -> Result<(String, Option<Calendar>), String> {
+  let mut calendar = None;
+  let mut words = Vec::new();
+
+  for word in s.split_whitespace() {
+    let mark = word.trim_matches(|c| c == '(' || c == ')' || c == ',').replace('.', "").to_uppercase();
+
+    let marked = match mark.as_str() {
+      "OS" => Calendar::Julian,
+      "NS" => Calendar::Gregorian,
+      _ => {
+        words.push(word);
+        continue;
+      }
+    };
+
+    if calendar.is_some() {
+      return Err("more than one calendar mark".to_string());
+    }
+
+    calendar = Some(marked);
+  }
+
+  Ok((words.join(" "), calendar))
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DateParser {
+  numeric_order: NumericOrder,
+  old_style_before: Option<NaiveDate>
+}
+
+impl DateParser {
+  pub fn new() -> DateParser {
+    DateParser {
+      numeric_order: NumericOrder::MonthFirst,
+      old_style_before: None
+    }
+  }
+
+  // applies to dates separated by slashes, dotted dates are always day first
+  pub fn numeric_order(mut self, order: NumericOrder) -> DateParser {
+    self.numeric_order = order;
+    self
+  }
+
+  // unmarked dates before `switch`, the first New Style day, are read as Old
+  // Style; see `calendar::british_switch`
+  pub fn old_style_before(mut self, switch: NaiveDate) -> DateParser {
+    self.old_style_before = Some(switch);
+    self
+  }
+
+  // an empty string is no date at all, anything unreadable is an error
+  pub fn parse(&self, s: &str) -> Result<Option<ParsedDate>, String> {
+    let s = s.trim();
+
+    if s.is_empty() {
+      return Ok(None);
+    }
+
+    self.parse_marked(s).map(Some).map_err(|err| format!("{} in \"{}\"", err, s))
+  }
+
+  fn parse_marked(&self, s: &str) -> Result<ParsedDate, String> {
+    let (s, mark) = strip_calendar_mark(s)?;
+
+    let mut literal = if s.chars().any(|c| c.is_alphabetic()) {
+      parse_named(&s)
+    } else if s.contains('/') {
+      parse_numeric(&s, '/', self.numeric_order)
+    } else if s.contains('.') {
+      parse_numeric(&s, '.', NumericOrder::DayFirst)
+    } else {
+      parse_iso(&s)
+    }?;
+
+    match (literal.calendar, mark) {
+      (Some(Calendar::Julian), Some(Calendar::Gregorian)) => return Err("dual dates are Old Style".to_string()),
+      (None, Some(mark)) => literal.calendar = Some(mark),
+      _ => {}
+    }
+
+    self.resolve(literal)
+  }
+
+  fn resolve(&self, literal: Literal) -> Result<ParsedDate, String> {
+    let (month, day, precision) = match (literal.month, literal.day) {
+      (None, _) => (1, 1, Precision::Year),
+      (Some(month), None) => (month, 1, Precision::Month),
+      (Some(month), Some(day)) => (month, day, Precision::Day)
+    };
+
+    let year = literal.year;
+
+    // unmarked, but before the switch
+    let switch = self.old_style_before.filter(|_| literal.calendar.is_none());
+    let assumed = switch.is_some_and(|switch| (year, month, day) < (switch.year(), switch.month(), switch.day()));
+
+    let calendar = match literal.calendar {
+      Some(calendar) => calendar,
+      None if assumed => Calendar::Julian,
+      None => Calendar::Gregorian
+    };
+
+    let date = calendar.normalize(year, month, day).ok_or(match calendar {
+      Calendar::Julian => format!("no such date: {}-{:02}-{:02} O.S.", year, month, day),
+      Calendar::Gregorian => format!("no such date: {}-{:02}-{:02}", year, month, day)
+    })?;
+
+    // the days dropped by the switch never happened where it applies
+    if assumed && switch.is_some_and(|switch| date >= switch) {
+      return Err(format!("{}-{:02}-{:02} was skipped by the switch to New Style", year, month, day));
+    }
+
+    if let Some(new_style_day) = literal.new_style_day {
+      if date.day() != new_style_day {
+        return Err(format!("day {} O.S. is day {} N.S., not {}", day, date.day(), new_style_day));
+      }
+    }
+
+    Ok(ParsedDate {
+      date: UTC.from_utc_datetime(&date.and_hms(0, 0, 0)),
+      precision,
+      calendar
+    })
+  }
+}
+
+impl Default for DateParser {
+  fn default() -> DateParser {
+    DateParser::new()
+  }
+}
+
+pub fn parse_date(s: &str) -> Result<Option<ParsedDate>, String> {
+  DateParser::new().parse(s)
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  fn day(s: &str) -> String {
+    parse_date(s).unwrap().unwrap().to_string()
+  }
+
+  #[test]
+  fn test_month_names() {
+    assert_eq!("1826-07-04", day("July 4 1826"));
+    assert_eq!("1826-07-04", day("Jul 4 1826"));
+    assert_eq!("1826-07-04", day("jul. 4, 1826"));
+    assert_eq!("1826-07-04", day("4 July 1826"));
+    assert_eq!("1826-07-04", day("4th July 1826"));
+    assert_eq!("1799-09-14", day("Sept 14 1799"));
+    assert_eq!("1799-12-14", day("December 14, 1799"));
+  }
+
+  #[test]
+  fn test_numeric() {
+    assert_eq!("1826-07-04", day("1826-07-04"));
+    assert_eq!("1826-07-04", day("7/4/1826"));
+    assert_eq!("1826-07-04", day("4.7.1826"));
+    assert_eq!("1826-07-25", day("25/7/1826"));
+
+    let eu = DateParser::new().numeric_order(NumericOrder::DayFirst);
+    assert_eq!("1826-07-04", eu.parse("04/07/1826").unwrap().unwrap().to_string());
+  }
+
+  #[test]
+  fn test_precision() {
+    let year = parse_date("1826").unwrap().unwrap();
+    assert_eq!(Precision::Year, year.precision);
+    assert_eq!("1826", year.to_string());
+
+    let month = parse_date("July 1826").unwrap().unwrap();
+    assert_eq!(Precision::Month, month.precision);
+    assert_eq!("1826-07", month.to_string());
+
+    assert_eq!(Precision::Month, parse_date("1826-07").unwrap().unwrap().precision);
+    assert_eq!(Precision::Month, parse_date("07/1826").unwrap().unwrap().precision);
+    assert_eq!(Precision::Day, parse_date("1826-07-04").unwrap().unwrap().precision);
+  }
+
+  #[test]
+  fn test_invalid() {
+    assert_eq!(Ok(None), parse_date("   "));
+
+    assert!(parse_date("Juyl 4 1826").is_err());
+    assert!(parse_date("Feb 30 1900").is_err());
+    assert!(parse_date("1826-13-01").is_err());
+    assert!(parse_date("July 4 26").is_err());
+    assert!(parse_date("4/7").is_err());
+    assert!(parse_date("July July 1826").is_err());
+    assert!(parse_date("Jul 4 1826").is_ok());
+
+    let err = parse_date("Juyl 4 1826").unwrap_err();
+    assert!(err.contains("Juyl 4 1826"));
+  }
+
+  #[test]
+  fn test_calendar_marks() {
+    let washington = parse_date("Feb 11 1732 O.S.").unwrap().unwrap();
+    assert_eq!("1732-02-22", washington.to_string());
+    assert_eq!(Calendar::Julian, washington.calendar);
+
+    assert_eq!("1732-02-22", day("Feb 11, 1732 (O.S.)"));
+    assert_eq!("1732-02-22", day("1732-02-11 OS"));
+    assert_eq!("1732-02-22", day("22 Feb 1732 N.S."));
+    assert_eq!(Calendar::Gregorian, parse_date("Feb 22 1732").unwrap().unwrap().calendar);
+
+    // Feb 29 1700 only exists in the old calendar
+    assert_eq!("1700-03-11", day("Feb 29 1700 O.S."));
+    assert!(parse_date("Feb 29 1700").is_err());
+    assert!(parse_date("Feb 11 1732 O.S. N.S.").is_err());
+  }
+
+  #[test]
+  fn test_dual_dates() {
+    assert_eq!("1732-02-22", day("Feb 11/22 1731/32"));
+    assert_eq!("1732-02-22", day("Feb 11 1731/32"));
+    assert_eq!("1732-02-22", day("11/22 February 1731/2"));
+    assert_eq!("1732-02-22", day("Feb 11/22 1732"));
+    assert_eq!("1800-03-01", day("Feb 18 1799/1800"));
+    assert_eq!(Calendar::Julian, parse_date("Feb 11/22 1731/32").unwrap().unwrap().calendar);
+
+    assert!(parse_date("Feb 11/23 1731/32").unwrap_err().contains("is day 22 N.S."));
+    assert!(parse_date("Feb 11 1731/33").is_err());
+    assert!(parse_date("Feb 11/22 1731/32 N.S.").is_err());
+    assert!(parse_date("Feb 11/x 1732").is_err());
+
+    assert_eq!("1732-04-04", day("Mar 24 1731/32"));
+    assert_eq!("1732-01-12", day("Jan 1 1731/32"));
+    assert!(parse_date("Jul 4 1731/32").unwrap_err().starts_with("dual year 1731/32 only applies from Jan 1 to Mar 24"));
+    assert!(parse_date("Mar 30 1731/32").is_err());
+    assert!(parse_date("Mar 25 1731/32").is_err());
+  }
+
+  #[test]
+  fn test_old_style_before() {
+    let british = DateParser::new().old_style_before(::calendar::british_switch());
+
+    assert_eq!("1732-02-22", british.parse("Feb 11 1732").unwrap().unwrap().to_string());
+    assert_eq!("1752-09-13", british.parse("Sep 2 1752").unwrap().unwrap().to_string());
+    assert_eq!("1752-09-14", british.parse("Sep 14 1752").unwrap().unwrap().to_string());
+    assert!(british.parse("Sep 3 1752").unwrap_err().contains("skipped"));
+
+    // marked dates keep their calendar
+    assert_eq!("1732-02-22", british.parse("Feb 22 1732 N.S.").unwrap().unwrap().to_string());
+    assert_eq!(Calendar::Gregorian, british.parse("1826-07-04").unwrap().unwrap().calendar);
+  }
+
+}
@@ -0,0 +1,339 @@
+// export.rs <weldale@gmail.com>
+//
+// CSV, JSON and SVG output for event streams and alive-count timelines, and
+// CSV and JSON for counts per region. Records are named through a label
+// function, so any `Lifespan` works.
+
+use chrono::{
+  DateTime,
+  UTC
+};
+
+use csv;
+
+use rustc_serialize::json::{
+  Json,
+  Object
+};
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use place::RegionCount;
+
+use {
+  Event,
+  EventType,
+  Lifespan,
+  alive_timeline
+};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+fn format_date(date: DateTime<UTC>) -> String {
+  date.format(DATE_FORMAT).to_string()
+}
+
+fn event_name(event_type: EventType) -> &'static str {
+  match event_type {
+    EventType::Born => "born",
+    EventType::Died => "died"
+  }
+}
+
+fn write_rows(rows: Vec<Vec<String>>) -> Result<String, String> {
+  let mut wtr = csv::Writer::from_memory();
+
+  for row in rows {
+    wtr.write(row.iter().map(|field| field.as_str())).map_err(|e| e.to_string())?;
+  }
+
+  Ok(wtr.into_string())
+}
+
+pub fn events_to_csv<'a, T, F>(events: &[Event<'a, T>], label: F) -> Result<String, String>
+where F: Fn(&T) -> String {
+  let mut rows = vec![vec!["date".to_string(), "event".to_string(), "name".to_string()]];
+
+  for event in events {
+    rows.push(vec![format_date(event.get_date()), event_name(event.get_event_type()).to_string(), label(event.get_value())]);
+  }
+
+  write_rows(rows)
+}
+
+pub fn timeline_to_csv(timeline: &[(DateTime<UTC>, usize)]) -> Result<String, String> {
+  let mut rows = vec![vec!["date".to_string(), "alive".to_string()]];
+
+  for &(date, alive) in timeline {
+    rows.push(vec![format_date(date), alive.to_string()]);
+  }
+
+  write_rows(rows)
+}
+
+// writes the rows as they come, for timelines too long to be held as a whole.
+// Returns the number of rows written
+pub fn write_timeline_csv<W, I>(timeline: I, output: W) -> Result<usize, String>
+where W: std::io::Write, I: IntoIterator<Item = Result<(DateTime<UTC>, usize), String>> {
+  let mut wtr = csv::Writer::from_writer(output);
+  let mut rows = 0;
+
+  wtr.write(["date", "alive"].iter()).map_err(|e| e.to_string())?;
+
+  for entry in timeline {
+    let (date, alive) = entry?;
+
+    wtr.write([format_date(date), alive.to_string()].iter()).map_err(|e| e.to_string())?;
+    rows += 1;
+  }
+
+  wtr.flush().map_err(|e| e.to_string())?;
+
+  Ok(rows)
+}
+
+pub fn events_to_json<'a, T, F>(events: &[Event<'a, T>], label: F) -> Json
+where F: Fn(&T) -> String {
+  Json::Array(events.iter().map(|event| {
+    let mut object = Object::new();
+    object.insert("date".to_string(), Json::String(format_date(event.get_date())));
+    object.insert("event".to_string(), Json::String(event_name(event.get_event_type()).to_string()));
+    object.insert("name".to_string(), Json::String(label(event.get_value())));
+    Json::Object(object)
+  }).collect())
+}
+
+pub fn timeline_to_json(timeline: &[(DateTime<UTC>, usize)]) -> Json {
+  Json::Array(timeline.iter().map(|&(date, alive)| {
+    let mut object = Object::new();
+    object.insert("date".to_string(), Json::String(format_date(date)));
+    object.insert("alive".to_string(), Json::U64(alive as u64));
+    Json::Object(object)
+  }).collect())
+}
+
+pub fn region_counts_to_csv(counts: &BTreeMap<String, RegionCount>) -> Result<String, String> {
+  let mut rows = vec![vec!["region".to_string(), "born".to_string(), "died".to_string(), "stayed".to_string()]];
+
+  for (region, count) in counts {
+    rows.push(vec![region.clone(), count.born.to_string(), count.died.to_string(), count.stayed.to_string()]);
+  }
+
+  write_rows(rows)
+}
+
+pub fn region_counts_to_json(counts: &BTreeMap<String, RegionCount>) -> Json {
+  Json::Array(counts.iter().map(|(region, count)| {
+    let mut object = Object::new();
+    object.insert("region".to_string(), Json::String(region.clone()));
+    object.insert("born".to_string(), Json::U64(count.born as u64));
+    object.insert("died".to_string(), Json::U64(count.died as u64));
+    object.insert("stayed".to_string(), Json::U64(count.stayed as u64));
+    Json::Object(object)
+  }).collect())
+}
+
+fn escape_xml(s: &str) -> String {
+  s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// a bar per record ordered by start, with the alive count drawn on top as a
+// step line scaled to the height of the bars
+#[derive(Clone, Debug)]
+pub struct GanttChart {
+  width: f64,
+  row_height: f64,
+  label_width: f64,
+  until: Option<DateTime<UTC>>
+}
+
+impl GanttChart {
+  pub fn new() -> GanttChart {
+    GanttChart {
+      width: 1000.0,
+      row_height: 14.0,
+      label_width: 160.0,
+      until: None
+    }
+  }
+
+  pub fn width(mut self, width: f64) -> GanttChart {
+    self.width = width;
+    self
+  }
+
+  pub fn row_height(mut self, row_height: f64) -> GanttChart {
+    self.row_height = row_height;
+    self
+  }
+
+  pub fn label_width(mut self, label_width: f64) -> GanttChart {
+    self.label_width = label_width;
+    self
+  }
+
+  // where bars of records without an end stop, defaults to the latest date in the data
+  pub fn until(mut self, until: DateTime<UTC>) -> GanttChart {
+    self.until = Some(until);
+    self
+  }
+
+  pub fn render<T, F>(&self, records: &[T], label: F) -> String
+  where T: Lifespan, F: Fn(&T) -> String {
+    let mut sorted: Vec<&T> = records.iter().collect();
+    sorted.sort_by_key(|record| record.start());
+
+    let height = self.row_height * sorted.len() as f64 + 2.0 * self.row_height;
+    let mut svg = String::new();
+
+    writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"sans-serif\" font-size=\"{}\">",
+      self.width, height, self.row_height * 0.8).unwrap();
+
+    let first = sorted.iter().map(|r| r.start()).min();
+    let last = sorted.iter().map(|r| std::cmp::max(r.start(), r.end().unwrap_or(r.start()))).max();
+
+    let (first, last) = match (first, last) {
+      (Some(first), Some(last)) => (first, std::cmp::max(self.until.unwrap_or(last), first)),
+      _ => {
+        svg.push_str("</svg>\n");
+        return svg;
+      }
+    };
+
+    let span = std::cmp::max((last - first).num_days(), 1) as f64;
+    let plot_width = self.width - self.label_width;
+    let x_of = |date: DateTime<UTC>| self.label_width + (date - first).num_days() as f64 / span * plot_width;
+
+    writeln!(svg, "  <text x=\"{}\" y=\"{}\">{}</text>", self.label_width, self.row_height, format_date(first)).unwrap();
+    writeln!(svg, "  <text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>", self.width, self.row_height, format_date(last)).unwrap();
+
+    for (row, record) in sorted.iter().enumerate() {
+      let y = self.row_height * (row + 1) as f64 + self.row_height * 0.5;
+      let start = x_of(record.start());
+      let end = x_of(std::cmp::min(record.end().unwrap_or(last), last));
+
+      writeln!(svg, "  <text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>", self.label_width - 4.0, y + self.row_height * 0.7, escape_xml(&label(record))).unwrap();
+      writeln!(svg, "  <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"steelblue\"/>", start, y, (end - start).max(1.0), self.row_height * 0.8).unwrap();
+    }
+
+    let timeline = alive_timeline(records);
+    let max_alive = timeline.iter().map(|&(_, alive)| alive).max().unwrap_or(0).max(1) as f64;
+    let bottom = height - self.row_height * 0.5;
+    let y_of = |alive: usize| bottom - alive as f64 / max_alive * (bottom - self.row_height * 1.5);
+
+    let mut points = vec![format!("{:.1},{:.1}", x_of(first), y_of(0))];
+    let mut alive = 0;
+
+    for &(date, count) in timeline.iter().filter(|&&(date, _)| date <= last) {
+      let x = x_of(date);
+      points.push(format!("{:.1},{:.1}", x, y_of(alive)));
+      points.push(format!("{:.1},{:.1}", x, y_of(count)));
+      alive = count;
+    }
+
+    points.push(format!("{:.1},{:.1}", x_of(last), y_of(alive)));
+
+    writeln!(svg, "  <polyline points=\"{}\" fill=\"none\" stroke=\"firebrick\" stroke-width=\"2\"/>", points.join(" ")).unwrap();
+    writeln!(svg, "  <text x=\"{}\" y=\"{:.1}\" fill=\"firebrick\">max alive: {}</text>", self.label_width + 4.0, y_of(max_alive as usize) - 4.0, max_alive).unwrap();
+
+    svg.push_str("</svg>\n");
+    svg
+  }
+}
+
+impl Default for GanttChart {
+  fn default() -> GanttChart {
+    GanttChart::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  use rustc_serialize::json::Json;
+
+  use {
+    President,
+    alive_timeline,
+    create_sorted_events,
+    read_csv
+  };
+
+  fn name(p: &President) -> String {
+    p.get_name().to_string()
+  }
+
+  #[test]
+  fn test_csv() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+
+    let events = events_to_csv(&create_sorted_events(&presidents), name).unwrap();
+    let mut lines = events.lines();
+
+    assert_eq!(Some("date,event,name"), lines.next());
+    assert_eq!(Some("1732-02-22,born,George Washington"), lines.next());
+
+    let timeline = timeline_to_csv(&alive_timeline(&presidents)).unwrap();
+    assert!(timeline.starts_with("date,alive\n1732-02-22,1\n1735-10-30,2\n"));
+
+    let mut written = Vec::new();
+    let rows = write_timeline_csv(alive_timeline(&presidents).into_iter().map(Ok), &mut written).unwrap();
+    assert_eq!(timeline, String::from_utf8(written).unwrap());
+    assert_eq!(timeline.lines().count() - 1, rows);
+  }
+
+  #[test]
+  fn test_json() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+
+    let json = events_to_json(&create_sorted_events(&presidents), name);
+    let parsed = Json::from_str(&json.to_string()).unwrap();
+
+    assert_eq!(json, parsed);
+    assert_eq!(Some("George Washington"), parsed[0]["name"].as_string());
+    assert_eq!(Some("born"), parsed[0]["event"].as_string());
+
+    let timeline = timeline_to_json(&alive_timeline(&presidents));
+    assert_eq!(Some(1), timeline[0]["alive"].as_u64());
+    assert_eq!(Some("1732-02-22"), timeline[0]["date"].as_string());
+  }
+
+  #[test]
+  fn test_svg() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+    let svg = GanttChart::new().render(&presidents, |p| format!("{} <{}>", p.get_name(), p.get_birth_place()));
+
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(presidents.len(), svg.matches("<rect ").count());
+    assert_eq!(1, svg.matches("<polyline ").count());
+    assert!(svg.contains("George Washington &lt;Westmoreland Co. Va.&gt;"));
+
+    let empty: Vec<President> = Vec::new();
+    assert_eq!(0, GanttChart::new().render(&empty, name).matches("<rect ").count());
+  }
+
+  #[test]
+  fn test_region_counts() {
+    use place::{
+      Level,
+      PlaceParser,
+      count_by_region
+    };
+
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+    let counts = count_by_region(&presidents, &PlaceParser::new(), Level::State, |p| Some(p.get_birth_place()), President::get_death_place);
+
+    let csv = region_counts_to_csv(&counts).unwrap();
+    assert!(csv.starts_with("region,born,died,stayed\nArkansas,1,0,0\n"));
+    assert!(csv.contains("\nVirginia,8,"));
+
+    let json = region_counts_to_json(&counts);
+    assert_eq!(Some("Arkansas"), json[0]["region"].as_string());
+    assert_eq!(Some(counts.len()), json.as_array().map(|regions| regions.len()));
+  }
+
+}
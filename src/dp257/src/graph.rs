@@ -0,0 +1,346 @@
+// graph.rs <weldale@gmail.com>
+//
+// Lifespans as an undirected graph: every record is a node, two nodes are
+// joined when their lifespans share at least one day, weighted by the number
+// of shared days. The edges come from a sweep over the event stream.
+
+use chrono::{
+  DateTime,
+  UTC
+};
+
+use std::collections::{
+  BTreeSet,
+  VecDeque
+};
+use std::fmt::Write;
+
+use {
+  EventType,
+  Lifespan,
+  create_sorted_events
+};
+use overlap::{
+  SameDayOrder,
+  day_interval
+};
+
+// carries the index of the record through the event stream
+struct Node<'a, T: 'a> {
+  index: usize,
+  record: &'a T
+}
+
+impl<'a, T: Lifespan> Lifespan for Node<'a, T> {
+  fn start(&self) -> DateTime<UTC> {
+    self.record.start()
+  }
+
+  fn end(&self) -> Option<DateTime<UTC>> {
+    self.record.end()
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edge {
+  // indices into the records, a < b
+  pub a: usize,
+  pub b: usize,
+  pub days: i64
+}
+
+pub struct OverlapGraph<'a, T: 'a> {
+  records: &'a [T],
+  edges: Vec<Edge>,
+  // (neighbour, days) per record
+  adjacency: Vec<Vec<(usize, i64)>>
+}
+
+fn escape_dot(s: &str) -> String {
+  s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl<'a, T: Lifespan> OverlapGraph<'a, T> {
+  // lifespans that haven't ended are counted up to `until`
+  pub fn new(records: &'a [T], order: SameDayOrder, until: DateTime<UTC>) -> OverlapGraph<'a, T> {
+    let intervals: Vec<Option<(DateTime<UTC>, DateTime<UTC>)>> = records.iter()
+      .map(|record| day_interval(record, order).map(|(start, end)| (start, end.unwrap_or(until))))
+      .collect();
+
+    let shared_days = |a: usize, b: usize| match (intervals[a], intervals[b]) {
+      (Some((start_a, end_a)), Some((start_b, end_b))) => {
+        (std::cmp::min(end_a, end_b) - std::cmp::max(start_a, start_b)).num_days()
+      },
+      _ => 0
+    };
+
+    let nodes: Vec<Node<T>> = records.iter().enumerate().map(|(index, record)| Node { index, record }).collect();
+    let events = create_sorted_events(&nodes);
+
+    let mut edges = Vec::new();
+    let mut alive = BTreeSet::new();
+    let mut i = 0;
+
+    // all births of a day meet everyone alive that morning, whether a death
+    // on that same day still counts is up to `shared_days`
+    while i < events.len() {
+      let date = events[i].get_date();
+      let day: Vec<_> = events[i..].iter().take_while(|event| event.get_date() == date).collect();
+      i += day.len();
+
+      for event in day.iter().filter(|event| event.get_event_type() == EventType::Born) {
+        let index = event.get_value().index;
+
+        for &other in &alive {
+          let days = shared_days(index, other);
+
+          if days > 0 {
+            edges.push(Edge { a: std::cmp::min(index, other), b: std::cmp::max(index, other), days });
+          }
+        }
+
+        alive.insert(index);
+      }
+
+      for event in day.iter().filter(|event| event.get_event_type() == EventType::Died) {
+        alive.remove(&event.get_value().index);
+      }
+    }
+
+    edges.sort_by_key(|edge| (edge.a, edge.b));
+
+    let mut adjacency = vec![Vec::new(); records.len()];
+
+    for edge in &edges {
+      adjacency[edge.a].push((edge.b, edge.days));
+      adjacency[edge.b].push((edge.a, edge.days));
+    }
+
+    OverlapGraph {
+      records,
+      edges,
+      adjacency
+    }
+  }
+
+  pub fn edges(&self) -> &[Edge] {
+    &self.edges
+  }
+
+  // the records overlapping the record at `index`, with the shared days
+  pub fn neighbours(&self, index: usize) -> Vec<(&'a T, i64)> {
+    self.adjacency[index].iter().map(|&(other, days)| (&self.records[other], days)).collect()
+  }
+
+  // groups of records linked by overlaps, each in the order of the records
+  pub fn components(&self) -> Vec<Vec<&'a T>> {
+    let mut seen = vec![false; self.records.len()];
+    let mut components = Vec::new();
+
+    for first in 0..self.records.len() {
+      if seen[first] {
+        continue;
+      }
+
+      seen[first] = true;
+
+      let mut component = vec![first];
+      let mut queue = VecDeque::new();
+      queue.push_back(first);
+
+      while let Some(index) = queue.pop_front() {
+        for &(other, _) in &self.adjacency[index] {
+          if !seen[other] {
+            seen[other] = true;
+            component.push(other);
+            queue.push_back(other);
+          }
+        }
+      }
+
+      component.sort();
+      components.push(component.into_iter().map(|index| &self.records[index]).collect());
+    }
+
+    components
+  }
+
+  // the most records that can be lined up so that each one starts after the
+  // one before and overlaps it
+  pub fn longest_chain(&self) -> Vec<&'a T> {
+    let mut order: Vec<usize> = (0..self.records.len()).collect();
+    order.sort_by_key(|&index| (self.records[index].start(), index));
+
+    let mut rank = vec![0; self.records.len()];
+
+    for (position, &index) in order.iter().enumerate() {
+      rank[index] = position;
+    }
+
+    // length of the longest chain ending in each record, and where it came from
+    let mut length = vec![1; self.records.len()];
+    let mut previous = vec![None; self.records.len()];
+
+    for &index in &order {
+      for &(other, _) in &self.adjacency[index] {
+        let later = self.records[other].start() > self.records[index].start();
+
+        if later && length[index] + 1 > length[other] {
+          length[other] = length[index] + 1;
+          previous[other] = Some(index);
+        }
+      }
+    }
+
+    let mut chain = Vec::new();
+    let mut current = order.iter().cloned().max_by_key(|&index| (length[index], std::cmp::Reverse(rank[index])));
+
+    while let Some(index) = current {
+      chain.push(&self.records[index]);
+      current = previous[index];
+    }
+
+    chain.reverse();
+    chain
+  }
+
+  // an undirected Graphviz graph, the edges are labelled with the shared days
+  pub fn to_dot<F: Fn(&T) -> String>(&self, label: F) -> String {
+    let mut dot = String::new();
+
+    dot.push_str("graph overlap {\n");
+
+    for (index, record) in self.records.iter().enumerate() {
+      writeln!(dot, "  n{} [label=\"{}\"];", index, escape_dot(&label(record))).unwrap();
+    }
+
+    for edge in &self.edges {
+      writeln!(dot, "  n{} -- n{} [weight={}, label=\"{}\"];", edge.a, edge.b, edge.days, edge.days).unwrap();
+    }
+
+    dot.push_str("}\n");
+    dot
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  use chrono;
+  use chrono::offset::TimeZone;
+
+  use President;
+  use read_csv;
+
+  struct Span {
+    name: &'static str,
+    from: DateTime<UTC>,
+    until: Option<DateTime<UTC>>
+  }
+
+  impl Lifespan for Span {
+    fn start(&self) -> DateTime<UTC> {
+      self.from
+    }
+
+    fn end(&self) -> Option<DateTime<UTC>> {
+      self.until
+    }
+  }
+
+  fn day(d: u32) -> DateTime<UTC> {
+    chrono::UTC.ymd(2016, 3, d).and_hms(0, 0, 0)
+  }
+
+  fn span(name: &'static str, from: u32, until: Option<u32>) -> Span {
+    Span { name, from: day(from), until: until.map(day) }
+  }
+
+  fn names(spans: Vec<&Span>) -> Vec<&'static str> {
+    spans.into_iter().map(|s| s.name).collect()
+  }
+
+  fn spans() -> Vec<Span> {
+    vec![
+      span("a", 1, Some(5)),
+      span("b", 5, Some(9)),
+      span("c", 3, Some(4)),
+      span("d", 8, Some(12)),
+      span("e", 20, Some(22))
+    ]
+  }
+
+  #[test]
+  fn test_edges() {
+    let spans = spans();
+    let graph = OverlapGraph::new(&spans, SameDayOrder::BirthsFirst, day(30));
+
+    assert_eq!(&[
+      Edge { a: 0, b: 1, days: 1 },
+      Edge { a: 0, b: 2, days: 2 },
+      Edge { a: 1, b: 3, days: 2 }
+    ], graph.edges());
+
+    assert_eq!(vec![("a", 1), ("d", 2)], graph.neighbours(1).into_iter().map(|(s, days)| (s.name, days)).collect::<Vec<_>>());
+
+    // a and b only touch on the day a ends
+    let strict = OverlapGraph::new(&spans, SameDayOrder::DeathsFirst, day(30));
+    assert!(!strict.edges().iter().any(|edge| (edge.a, edge.b) == (0, 1)));
+  }
+
+  #[test]
+  fn test_components_and_chain() {
+    let spans = spans();
+    let graph = OverlapGraph::new(&spans, SameDayOrder::BirthsFirst, day(30));
+
+    let components: Vec<Vec<&str>> = graph.components().into_iter().map(names).collect();
+    assert_eq!(vec![vec!["a", "b", "c", "d"], vec!["e"]], components);
+
+    assert_eq!(vec!["a", "b", "d"], names(graph.longest_chain()));
+
+    let strict = OverlapGraph::new(&spans, SameDayOrder::DeathsFirst, day(30));
+    assert_eq!(3, strict.components().len());
+    assert_eq!(2, strict.longest_chain().len());
+
+    let empty: Vec<Span> = Vec::new();
+    assert!(OverlapGraph::new(&empty, SameDayOrder::BirthsFirst, day(30)).longest_chain().is_empty());
+  }
+
+  #[test]
+  fn test_dot() {
+    let spans = vec![span("a \"x\"", 1, Some(5)), span("b", 5, Some(9))];
+    let dot = OverlapGraph::new(&spans, SameDayOrder::BirthsFirst, day(30)).to_dot(|s| s.name.to_string());
+
+    assert_eq!("graph overlap {\n  n0 [label=\"a \\\"x\\\"\"];\n  n1 [label=\"b\"];\n  n0 -- n1 [weight=1, label=\"1\"];\n}\n", dot);
+  }
+
+  #[test]
+  fn test_presidents() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+    let until = chrono::UTC.ymd(2016, 3, 1).and_hms(0, 0, 0);
+    let graph = OverlapGraph::new(&presidents, SameDayOrder::BirthsFirst, until);
+
+    // everyone's life overlapped with someone else's
+    assert_eq!(1, graph.components().len());
+
+    let chain = graph.longest_chain();
+    assert_eq!("George Washington", chain[0].get_name());
+
+    for pair in chain.windows(2) {
+      assert!(pair[0].start() < pair[1].start());
+      assert!(pair[0].end().is_none_or(|end| end >= pair[1].start()));
+    }
+
+    // John Adams and Jefferson both died on July 4 1826
+    let adams = graph.neighbours(1);
+    let jefferson = adams.iter().find(|&&(p, _)| p.get_name() == "Thomas Jefferson").unwrap();
+    assert_eq!((chrono::UTC.ymd(1826, 7, 5).and_hms(0, 0, 0) - presidents[2].start()).num_days(), jefferson.1);
+
+    let dot = graph.to_dot(|p: &President| p.get_name().to_string());
+    assert_eq!(presidents.len(), dot.matches("[label=\"").count());
+    assert_eq!(graph.edges().len(), dot.matches(" -- ").count());
+  }
+
+}
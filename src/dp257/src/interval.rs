@@ -0,0 +1,239 @@
+// interval.rs <weldale@gmail.com>
+//
+// Static interval tree over lifespans. The intervals are sorted by start and
+// form an implicit balanced tree, every node knows the latest end in its
+// subtree so queries can skip subtrees that ended too early.
+
+use chrono::{
+  DateTime,
+  Duration,
+  UTC
+};
+
+use Lifespan;
+use overlap::{
+  SameDayOrder,
+  day_interval
+};
+
+// None is an interval that hasn't ended
+type End = Option<DateTime<UTC>>;
+
+fn ends_after(end: End, date: DateTime<UTC>) -> bool {
+  end.is_none_or(|end| end > date)
+}
+
+fn later_end(a: End, b: End) -> End {
+  match (a, b) {
+    (Some(a), Some(b)) => Some(std::cmp::max(a, b)),
+    _ => None
+  }
+}
+
+struct Entry {
+  start: DateTime<UTC>,
+  end: End,
+  index: usize
+}
+
+pub struct IntervalIndex<'a, T: 'a> {
+  records: &'a [T],
+  order: SameDayOrder,
+  entries: Vec<Entry>,
+  max_end: Vec<End>
+}
+
+impl<'a, T: Lifespan> IntervalIndex<'a, T> {
+  pub fn new(records: &'a [T], order: SameDayOrder) -> IntervalIndex<'a, T> {
+    let mut entries: Vec<Entry> = records.iter()
+      .enumerate()
+      .filter_map(|(index, record)| day_interval(record, order).map(|(start, end)| Entry { start, end, index }))
+      .collect();
+
+    entries.sort_by_key(|entry| (entry.start, entry.index));
+
+    let mut index = IntervalIndex {
+      records,
+      order,
+      max_end: entries.iter().map(|entry| entry.end).collect(),
+      entries
+    };
+
+    let len = index.entries.len();
+    index.build(0, len);
+    index
+  }
+
+  // returns the latest end of the subtree, None for an empty one
+  fn build(&mut self, lo: usize, hi: usize) -> Option<End> {
+    if lo >= hi {
+      return None;
+    }
+
+    let mid = lo + (hi - lo) / 2;
+    let children = [self.build(lo, mid), self.build(mid + 1, hi)];
+
+    self.max_end[mid] = children.iter().flatten().fold(self.entries[mid].end, |max, &end| later_end(max, end));
+    Some(self.max_end[mid])
+  }
+
+  // visits every interval that overlaps [from, to), to None being open ended
+  fn visit<'s, F>(&'s self, lo: usize, hi: usize, from: DateTime<UTC>, to: End, f: &mut F)
+  where F: FnMut(&'s Entry) {
+    if lo >= hi {
+      return;
+    }
+
+    let mid = lo + (hi - lo) / 2;
+
+    if !ends_after(self.max_end[mid], from) {
+      return;
+    }
+
+    self.visit(lo, mid, from, to, f);
+
+    let entry = &self.entries[mid];
+
+    // everything from here on starts too late
+    if to.is_some_and(|to| entry.start >= to) {
+      return;
+    }
+
+    if ends_after(entry.end, from) {
+      f(entry);
+    }
+
+    self.visit(mid + 1, hi, from, to, f);
+  }
+
+  fn overlapping_entries(&self, from: DateTime<UTC>, to: End) -> Vec<&Entry> {
+    let mut result = Vec::new();
+    self.visit(0, self.entries.len(), from, to, &mut |entry| result.push(entry));
+    result
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  // records alive at some point of the half-open range [from, to), ordered by start
+  pub fn overlapping(&self, from: DateTime<UTC>, to: End) -> Vec<&'a T> {
+    self.overlapping_entries(from, to).into_iter().map(|entry| &self.records[entry.index]).collect()
+  }
+
+  pub fn alive_on(&self, date: DateTime<UTC>) -> Vec<&'a T> {
+    self.overlapping(date, Some(date + Duration::days(1)))
+  }
+
+  // records alive on every day from `from` up to and including `to`
+  pub fn alive_throughout(&self, from: DateTime<UTC>, to: DateTime<UTC>) -> Vec<&'a T> {
+    self.overlapping_entries(from, Some(from + Duration::days(1))).into_iter()
+      .filter(|entry| ends_after(entry.end, to))
+      .map(|entry| &self.records[entry.index])
+      .collect()
+  }
+
+  // records whose lifespan shares at least one day with `record`'s, without
+  // `record` itself
+  pub fn overlapped_with(&self, record: &T) -> Vec<&'a T> {
+    let (start, end) = match day_interval(record, self.order) {
+      Some(interval) => interval,
+      None => return Vec::new()
+    };
+
+    self.overlapping(start, end).into_iter()
+      .filter(|other| !std::ptr::eq(*other, record))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  use chrono::offset::TimeZone;
+
+  use Lifespan;
+  use President;
+  use read_csv;
+  use overlap::SameDayOrder;
+
+  fn date(y: i32, m: u32, d: u32) -> DateTime<UTC> {
+    chrono::UTC.ymd(y, m, d).and_hms(0, 0, 0)
+  }
+
+  fn names(presidents: Vec<&President>) -> Vec<&str> {
+    presidents.into_iter().map(|p| p.get_name()).collect()
+  }
+
+  fn brute_force(presidents: &[President], from: DateTime<UTC>, to: DateTime<UTC>) -> Vec<&str> {
+    let mut result: Vec<&President> = presidents.iter()
+      .filter(|p| p.start() < to && p.end().is_none_or(|end| end >= from))
+      .collect();
+
+    result.sort_by_key(|p| p.start());
+    names(result)
+  }
+
+  #[test]
+  fn test_alive_on() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+    let index = IntervalIndex::new(&presidents, SameDayOrder::BirthsFirst);
+
+    assert_eq!(presidents.len(), index.len());
+
+    assert_eq!(vec!["George Washington"], names(index.alive_on(date(1735, 1, 1))));
+
+    // Adams and Jefferson both died on the 4th of July 1826
+    let alive = names(index.alive_on(date(1826, 7, 4)));
+    assert!(alive.contains(&"John Adams") && alive.contains(&"Thomas Jefferson"));
+
+    let alive = names(index.alive_on(date(1826, 7, 5)));
+    assert!(!alive.contains(&"John Adams") && !alive.contains(&"Thomas Jefferson"));
+
+    for year in (1730..2020).step_by(7) {
+      let day = date(year, 7, 4);
+      assert_eq!(brute_force(&presidents, day, day + Duration::days(1)), names(index.alive_on(day)));
+    }
+
+    let strict = IntervalIndex::new(&presidents, SameDayOrder::DeathsFirst);
+    assert!(!names(strict.alive_on(date(1826, 7, 4))).contains(&"John Adams"));
+  }
+
+  #[test]
+  fn test_alive_throughout() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+    let index = IntervalIndex::new(&presidents, SameDayOrder::BirthsFirst);
+
+    let alive = names(index.alive_throughout(date(1800, 1, 1), date(1826, 7, 4)));
+    assert!(alive.contains(&"John Adams"));
+    assert!(!alive.contains(&"George Washington"));
+    assert!(!alive.contains(&"Abraham Lincoln"));
+
+    assert!(index.alive_throughout(date(1700, 1, 1), date(2000, 1, 1)).is_empty());
+
+    let throughout = index.alive_throughout(date(1990, 1, 1), date(2000, 1, 1)).len();
+    assert!(throughout <= index.alive_on(date(1990, 1, 1)).len());
+  }
+
+  #[test]
+  fn test_overlapped_with() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+    let index = IntervalIndex::new(&presidents, SameDayOrder::BirthsFirst);
+
+    let washington = &presidents[0];
+    let overlapped = names(index.overlapped_with(washington));
+
+    assert!(!overlapped.contains(&"George Washington"));
+    assert_eq!(brute_force(&presidents, washington.start(), washington.end().unwrap() + Duration::days(1)).len() - 1, overlapped.len());
+
+    let obama = presidents.iter().find(|p| p.get_name() == "Barack Obama").unwrap();
+    assert!(!names(index.overlapped_with(obama)).contains(&"Abraham Lincoln"));
+  }
+
+}
@@ -4,16 +4,44 @@ extern crate csv;
 extern crate chrono;
 extern crate rustc_serialize;
 
+pub mod calendar;
+pub mod columns;
+pub mod date;
+pub mod export;
+pub mod graph;
+pub mod interval;
+pub mod overlap;
+pub mod place;
+pub mod stats;
+pub mod stream;
+
 use chrono::{
   DateTime,
-  UTC,
-  TimeZone
+  UTC
 };
 
-use std::collections::HashMap;
+use rustc_serialize::{
+  Decodable,
+  Decoder
+};
 
+use std::cell::RefCell;
+use std::collections::{
+  HashMap,
+  VecDeque
+};
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::rc::Rc;
+
+use date::{
+  DateParser,
+  ParsedDate
+};
 
-#[derive(RustcDecodable, Debug)]
+#[derive(Debug)]
 struct ParsedPresident {
   name: String,
   birth_date: String,
@@ -22,16 +50,72 @@ struct ParsedPresident {
   death_place: String
 }
 
+// the derive for this is gone from the compiler, so the fields are read by hand
+impl Decodable for ParsedPresident {
+  fn decode<D: Decoder>(d: &mut D) -> Result<ParsedPresident, D::Error> {
+    d.read_struct("ParsedPresident", 5, |d| {
+      Ok(ParsedPresident {
+        name: d.read_struct_field("name", 0, Decodable::decode)?,
+        birth_date: d.read_struct_field("birth_date", 1, Decodable::decode)?,
+        birth_place: d.read_struct_field("birth_place", 2, Decodable::decode)?,
+        death_date: d.read_struct_field("death_date", 3, Decodable::decode)?,
+        death_place: d.read_struct_field("death_place", 4, Decodable::decode)?
+      })
+    })
+  }
+}
+
 #[derive(Debug)]
 pub struct President {
   name: String,
-  birth_date: DateTime<UTC>,
+  birth_date: ParsedDate,
   birth_place: String,
-  death_date: Option<DateTime<UTC>>,
+  death_date: Option<ParsedDate>,
   death_place: Option<String>
 }
 
-#[derive(Debug)]
+impl President {
+  pub fn get_name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn get_birth_date(&self) -> ParsedDate {
+    self.birth_date
+  }
+
+  pub fn get_birth_place(&self) -> &str {
+    &self.birth_place
+  }
+
+  pub fn get_death_date(&self) -> Option<ParsedDate> {
+    self.death_date
+  }
+
+  pub fn get_death_place(&self) -> Option<&str> {
+    self.death_place.as_deref()
+  }
+}
+
+// anything that starts at some date and may have ended since, like a life,
+// an employment, a server's uptime or a lease
+pub trait Lifespan {
+  fn start(&self) -> DateTime<UTC>;
+
+  fn end(&self) -> Option<DateTime<UTC>>;
+}
+
+// so that borrowed records can be streamed as well
+impl<T: Lifespan> Lifespan for &T {
+  fn start(&self) -> DateTime<UTC> {
+    (**self).start()
+  }
+
+  fn end(&self) -> Option<DateTime<UTC>> {
+    (**self).end()
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum EventType {
   Born,
   Died
@@ -52,34 +136,45 @@ impl<'a, T: 'a> Event<'a, T> {
       event_type: event_type
     }
   }
+
+  pub fn get_value(&self) -> &'a T {
+    self.value
+  }
+
+  pub fn get_date(&self) -> DateTime<UTC> {
+    self.date
+  }
+
+  pub fn get_event_type(&self) -> EventType {
+    self.event_type
+  }
 }
 
 trait IntoEvent : Sized {
   fn into_event<'a>(&'a self, event_type: EventType) -> Option<Event<'a, Self>>;
 }
 
-fn replace_abbrev(s: &String) -> String {
-  s.replace("July", "Jul").replace("June", "Jun")
-}
-
-fn parse_date(s: &String) -> Option<DateTime<UTC>> {
-  let trim_s = s.trim().to_string();
-  if trim_s.len() == 0 {
-    return Option::None;
+impl<T: Lifespan> IntoEvent for T {
+  fn into_event<'a>(&'a self, event_type: EventType) -> Option<Event<'a, T>> {
+    match event_type {
+      EventType::Born => Some(Event::new(self, self.start(), EventType::Born)),
+      EventType::Died => self.end().map(|date| Event::new(self, date, EventType::Died))
+    }
   }
-
-  let replaced = replace_abbrev(&trim_s);
-  let with_time = format!("{} 00:00:00", replaced);
-
-  Some(UTC.datetime_from_str(&with_time, "%b %e %Y %H:%M:%S").unwrap())
 }
 
-impl From<ParsedPresident> for President {
+impl TryFrom<ParsedPresident> for President {
+  type Error = String;
 
-  fn from(p: ParsedPresident) -> President {
+  fn try_from(p: ParsedPresident) -> Result<President, String> {
 
-    let birth_date = parse_date(&p.birth_date).unwrap();
-    let death_date = parse_date(&p.death_date);
+    let parser = DateParser::new();
+    let birth_date = parser.parse(&p.birth_date)?.ok_or("missing birth date")?;
+    let death_date = parser.parse(&p.death_date)?;
+
+    if death_date.is_some_and(|death| death.date < birth_date.date) {
+      return Err(format!("death date {} is before birth date {}", p.death_date.trim(), p.birth_date.trim()));
+    }
 
     let death_place_trim = p.death_place.trim().to_string();
 
@@ -89,45 +184,161 @@ impl From<ParsedPresident> for President {
       Option::Some(death_place_trim)
     };
 
-    President {
+    Ok(President {
       name: p.name.trim().to_string(),
       birth_date: birth_date,
       birth_place: p.birth_place.trim().to_string(),
       death_date: death_date,
       death_place: death_place
+    })
+  }
+}
+
+impl Lifespan for President {
+  fn start(&self) -> DateTime<UTC> {
+    self.birth_date.date
+  }
+
+  fn end(&self) -> Option<DateTime<UTC>> {
+    self.death_date.map(|date| date.date)
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RowError {
+  pub line: usize,
+  pub message: String
+}
+
+impl fmt::Display for RowError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "line {}: {}", self.line, self.message)
+  }
+}
+
+// the rows that could be read, and why the others couldn't
+#[derive(Debug)]
+pub struct LoadReport {
+  pub presidents: Vec<President>,
+  pub errors: Vec<RowError>
+}
+
+// the line breaks the csv reader hasn't gone past yet, as (offset, byte)
+#[derive(Default)]
+struct LineBreaks {
+  pending: VecDeque<(u64, u8)>,
+  read: u64,
+  // line feeds the reader has gone past
+  passed: usize
+}
+
+impl LineBreaks {
+  // the line of the record following `offset`, the end of the record before.
+  // The reader skips the rest of that record's line break and blank lines
+  fn line_at(&mut self, offset: u64) -> usize {
+    let mut skipped = offset;
+
+    while let Some(&(at, byte)) = self.pending.front() {
+      if at == skipped {
+        skipped += 1;
+      } else if at > offset {
+        break;
+      }
+
+      self.pending.pop_front();
+
+      if byte == b'\n' {
+        self.passed += 1;
+      }
     }
+
+    self.passed + 1
   }
 }
 
-impl IntoEvent for President {
-  fn into_event<'a>(&'a self, event_type: EventType) -> Option<Event<'a, President>> {
-    match event_type {
-      EventType::Born => Some(Event::new(self, self.birth_date, EventType::Born)),
-      EventType::Died => self.death_date.map(|date| Event::new(self, date, EventType::Died))
+// a record can span several lines when a quoted field holds line breaks, so
+// the input is watched on its way into the csv reader
+struct LineCounter<R> {
+  inner: R,
+  breaks: Rc<RefCell<LineBreaks>>
+}
+
+impl<R: Read> Read for LineCounter<R> {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    let n = self.inner.read(buf)?;
+    let mut breaks = self.breaks.borrow_mut();
+
+    for (i, &byte) in buf[..n].iter().enumerate() {
+      if byte == b'\n' || byte == b'\r' {
+        let at = breaks.read + i as u64;
+        breaks.pending.push_back((at, byte));
+      }
     }
+
+    breaks.read += n as u64;
+
+    Ok(n)
   }
 }
 
-pub fn read_csv(file_path: &str) -> Result<Vec<President>, String> {
-  let mut rdr = try!(csv::Reader::from_file(file_path).map_err(|e| e.to_string())).has_headers(true);
+fn read_records<R: Read>(input: R) -> LoadReport {
+  let breaks = Rc::new(RefCell::new(LineBreaks::default()));
+  let counter = LineCounter { inner: input, breaks: breaks.clone() };
+
+  // a short row would otherwise end the whole read
+  let mut rdr = csv::Reader::from_reader(counter).has_headers(true).flexible(true);
 
-  let mut result: Vec<President> = Vec::new();
+  let mut report = LoadReport {
+    presidents: Vec::new(),
+    errors: Vec::new()
+  };
 
-  for record in rdr.decode() {
-    let parsed: ParsedPresident = try!(record.map_err(|e| e.to_string()));
-    let president = President::from(parsed);
-    result.push(president);
+  // offsets only point at records once the header is behind the reader
+  if let Err(e) = rdr.headers() {
+    report.errors.push(RowError { line: 1, message: e.to_string() });
+    return report;
   }
 
-  Ok(result)
+  loop {
+    let offset = rdr.byte_offset();
+
+    let record: csv::Result<ParsedPresident> = match rdr.decode().next() {
+      Some(record) => record,
+      None => break
+    };
+
+    let line = breaks.borrow_mut().line_at(offset);
+
+    let president = record
+      .map_err(|e| e.to_string())
+      .and_then(President::try_from);
+
+    match president {
+      Ok(president) => report.presidents.push(president),
+      Err(message) => report.errors.push(RowError { line, message })
+    }
+  }
+
+  report
 }
 
-pub fn create_events<'a>(presidents: &'a Vec<President>) -> Vec<Event<'a, President>> {
-  let mut result: Vec<Event<'a, President>> = Vec::new();
+// only a file that can't be opened fails as a whole
+pub fn read_csv(file_path: &str) -> Result<LoadReport, String> {
+  let file = File::open(file_path).map_err(|e| e.to_string())?;
+
+  Ok(read_records(file))
+}
+
+pub fn read_csv_str(data: &str) -> LoadReport {
+  read_records(data.as_bytes())
+}
 
-  for president in presidents {
-    result.push( president.into_event(EventType::Born).unwrap() );
-    if let Some(event) = president.into_event(EventType::Died) {
+pub fn create_events<'a, T: Lifespan>(records: &'a [T]) -> Vec<Event<'a, T>> {
+  let mut result: Vec<Event<'a, T>> = Vec::new();
+
+  for record in records {
+    result.push( record.into_event(EventType::Born).unwrap() );
+    if let Some(event) = record.into_event(EventType::Died) {
       result.push(event);
     }
   }
@@ -135,14 +346,57 @@ pub fn create_events<'a>(presidents: &'a Vec<President>) -> Vec<Event<'a, Presid
   result
 }
 
-pub fn create_alive_map<'a>(events: &Vec<Event<'a, President>>) -> HashMap<i32, isize> {
+fn is_reversed<T: Lifespan>(record: &T) -> bool {
+  record.end().is_some_and(|end| end < record.start())
+}
+
+// all events ordered by date, events on the same date keep the order of the records
+pub fn create_sorted_events<T: Lifespan>(records: &[T]) -> Vec<Event<'_, T>> {
+  let mut events = create_events(records);
+  events.sort_by_key(|e| e.date);
+  events
+}
+
+// the number of alive records after all events of each date took place,
+// records that end before they start are left out
+pub fn alive_timeline<T: Lifespan>(records: &[T]) -> Vec<(DateTime<UTC>, usize)> {
+  let mut timeline: Vec<(DateTime<UTC>, usize)> = Vec::new();
+  let mut count = 0;
+
+  for event in create_sorted_events(records) {
+    if is_reversed(event.value) {
+      continue;
+    }
+
+    match event.event_type {
+      EventType::Born => count += 1,
+      EventType::Died => count -= 1
+    }
+
+    match timeline.last_mut() {
+      Some(last) if last.0 == event.date => last.1 = count,
+      _ => timeline.push((event.date, count))
+    }
+  }
+
+  timeline
+}
+
+// the running count at the last event of each year, see `overlap` for exact
+// intervals; the events don't need to be sorted. Years are counted in New
+// Style, however the dates were written. Like in `alive_timeline` records
+// that end before they start are left out
+pub fn create_alive_map<'a, T: Lifespan>(events: &[Event<'a, T>]) -> HashMap<i32, isize> {
   use chrono::Datelike;
 
   let mut alive_map = HashMap::new();
 
   let mut count = 0;
 
-  for event in events {
+  let mut sorted: Vec<&Event<'a, T>> = events.iter().filter(|e| !is_reversed(e.value)).collect();
+  sorted.sort_by_key(|e| e.date);
+
+  for event in sorted {
 
     let year = event.date.year();
 
@@ -161,10 +415,6 @@ pub fn create_alive_map<'a>(events: &Vec<Event<'a, President>>) -> HashMap<i32,
   alive_map
 }
 
-fn main() {
-
-}
-
 #[cfg(test)]
 mod tests {
 
@@ -175,12 +425,55 @@ mod tests {
 
   #[test]
   fn test_csv() {
-    read_csv("data/presidents.csv");
+    let report = read_csv("data/presidents.csv").unwrap();
+
+    assert_eq!(Vec::<RowError>::new(), report.errors);
+    assert_eq!("George Washington", report.presidents[0].get_name());
+    assert_eq!("1799-12-14", report.presidents[0].get_death_date().unwrap().to_string());
+  }
+
+  #[test]
+  fn test_csv_row_errors() {
+    let data = "PRESIDENT, BIRTH DATE, BIRTH PLACE, DEATH DATE, LOCATION OF DEATH\n\
+                A, Juyl 4 1800, X, , \n\
+                B, 1800, X, Sep 1850, Y\n\
+                C, , X, , \n\
+                D, June 5 1801, X\n\
+                E, 1802-03-04, X, 31.12.1870, Z\n\
+                F, July 4 1826, X, 1800, Y\n";
+
+    let report = read_csv_str(data);
+
+    assert_eq!(2, report.presidents.len());
+    assert_eq!("E", report.presidents[1].get_name());
+    assert_eq!(date::Precision::Year, report.presidents[0].get_birth_date().precision);
+
+    let lines: Vec<usize> = report.errors.iter().map(|e| e.line).collect();
+    assert_eq!(vec![2, 4, 5, 7], lines);
+    assert!(report.errors[0].to_string().starts_with("line 2: unknown month: Juyl"));
+    assert_eq!("death date 1800 is before birth date July 4 1826", report.errors[3].message);
+  }
+
+  #[test]
+  fn test_csv_row_error_lines() {
+    let data = "PRESIDENT, BIRTH DATE, BIRTH PLACE, DEATH DATE, LOCATION OF DEATH\n\
+                \n\
+                A, Juyl 4 1800, X, , \n\
+                B, 1800, X, Sep 1850, Y\n\
+                C, 1801,\"Two\nLines\", , \n\
+                D, June 5 1801, X, 1700, \n";
+
+    let report = read_csv_str(data);
+
+    let lines: Vec<usize> = report.errors.iter().map(|e| e.line).collect();
+    assert_eq!(vec![3, 7], lines);
+    assert_eq!(2, report.presidents.len());
+    assert_eq!("Two\nLines", report.presidents[1].birth_place);
   }
 
   #[test]
   fn test_into_events() {
-    let presidents = read_csv("data/presidents.csv").unwrap();
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
     let mut events = create_events(&presidents);
 
     events.sort_by_key(|e| e.date);
@@ -198,10 +491,74 @@ mod tests {
 
   }
 
+  struct Lease {
+    from: DateTime<UTC>,
+    until: Option<DateTime<UTC>>
+  }
+
+  impl Lifespan for Lease {
+    fn start(&self) -> DateTime<UTC> {
+      self.from
+    }
+
+    fn end(&self) -> Option<DateTime<UTC>> {
+      self.until
+    }
+  }
+
+  fn day(d: u32) -> DateTime<UTC> {
+    chrono::UTC.ymd(2016, 3, d).and_hms(0, 0, 0)
+  }
+
+  #[test]
+  fn test_generic_lifespan() {
+    let leases = vec![
+      Lease { from: day(1), until: Some(day(5)) },
+      Lease { from: day(3), until: None },
+      Lease { from: day(5), until: Some(day(9)) },
+      Lease { from: day(2), until: Some(day(3)) }
+    ];
+
+    let events = create_sorted_events(&leases);
+
+    assert_eq!(7, events.len());
+    assert_eq!(day(1), events[0].get_date());
+    assert_eq!(EventType::Born, events[0].get_event_type());
+    assert!(events[0].get_value().until.is_some());
+
+    let timeline = alive_timeline(&leases);
+
+    assert_eq!(vec![(day(1), 1), (day(2), 2), (day(3), 2), (day(5), 2), (day(9), 1)], timeline);
+
+    let reversed = vec![Lease { from: day(5), until: Some(day(1)) }, Lease { from: day(2), until: Some(day(2)) }];
+    assert_eq!(vec![(day(2), 0)], alive_timeline(&reversed));
+    assert_eq!(Some(&0), create_alive_map(&create_events(&reversed)).get(&2016));
+
+    // a reversed record alone leaves no year behind
+    let reversed = vec![Lease { from: day(5), until: Some(day(1)) }];
+    assert!(create_alive_map(&create_events(&reversed)).is_empty());
+  }
+
   #[test]
   fn test_parsing() {
     let time = chrono::UTC.datetime_from_str("Jul 22 1732 00:00:00", "%b %e %Y %H:%M:%S").unwrap().date();
     println!("{:?}", time);
   }
 
+  #[test]
+  fn test_alive_map_old_style() {
+    let new_style = "PRESIDENT, BIRTH DATE, BIRTH PLACE, DEATH DATE, LOCATION OF DEATH\n\
+                     A, Feb 22 1732, X, Dec 14 1799, Y\n\
+                     B, Dec 29 1699, X, Jan 3 1733, Y\n";
+    let old_style = "PRESIDENT, BIRTH DATE, BIRTH PLACE, DEATH DATE, LOCATION OF DEATH\n\
+                     A, Feb 11/22 1731/32, X, Dec 14 1799, Y\n\
+                     B, Dec 19 1699 O.S., X, Dec 23 1732 O.S., Y\n";
+
+    let new_style = read_csv_str(new_style).presidents;
+    let old_style = read_csv_str(old_style).presidents;
+
+    assert_eq!(create_alive_map(&create_events(&new_style)), create_alive_map(&create_events(&old_style)));
+    assert_eq!(Some(&1), create_alive_map(&create_events(&old_style)).get(&1733));
+  }
+
 }
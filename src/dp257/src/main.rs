@@ -0,0 +1,501 @@
+// main.rs <weldale@gmail.com>
+
+extern crate chrono;
+extern crate dp257;
+
+use chrono::UTC;
+
+use std::env;
+use std::fs::File;
+use std::io::{
+  BufWriter,
+  Read,
+  Write
+};
+use std::process;
+use std::str::FromStr;
+
+use dp257::{
+  alive_timeline,
+  create_sorted_events
+};
+use dp257::columns::{
+  Column,
+  DateColumn,
+  DateFormat,
+  Record,
+  RecordReport,
+  Schema,
+  parse_delimiter,
+  read_csv_schema,
+  stream_csv_schema
+};
+use dp257::calendar::british_switch;
+use dp257::date::{
+  DateParser,
+  NumericOrder
+};
+use dp257::export::{
+  GanttChart,
+  events_to_csv,
+  events_to_json,
+  timeline_to_csv,
+  timeline_to_json,
+  write_timeline_csv
+};
+use dp257::graph::OverlapGraph;
+use dp257::interval::IntervalIndex;
+use dp257::overlap::{
+  SameDayOrder,
+  peak_intervals
+};
+use dp257::stream::{
+  SweepConfig,
+  external_alive_timeline
+};
+
+const USAGE: &str = "\
+usage: dp257 <command> [options] FILE
+
+commands:
+  load               read FILE and report every row that can't be used
+  peak               show the intervals with the most people alive at once
+  alive-on DATE      list everyone alive on DATE
+  export             write the events or the alive timeline of FILE
+
+columns, given as a header name or a zero based index. Dates can name their
+format after a |, like \"born|%d.%m.%Y\":
+  --name COL            (default 0)
+  --birth COL           (default 1)
+  --birth-place COL     (default 2, - for none)
+  --death COL           (default 3, - for none)
+  --death-place COL     (default 4, - for none)
+  --attribute NAME=COL  keep another column
+  --delimiter CHAR      (default ,)
+  --schema PATH         read all of the above from a schema file, which can't
+                        be combined with the other column options, --day-first
+                        or --old-style
+
+options:
+  --day-first        read 04/07/1826 as the 4th of July
+  --old-style        read unmarked dates before 14 Sep 1752 as Old Style
+  --deaths-first     a death and a birth on the same day don't overlap
+  --format FORMAT    export as csv, json, svg or dot (default csv)
+  --what DATA        export events or timeline (default timeline)
+  --output PATH      export to PATH instead of stdout
+  --budget EVENTS    export the timeline as csv while holding at most EVENTS
+                     events in memory, spilling the rest to temporary files";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+  Csv,
+  Json,
+  Svg,
+  // the overlap graph
+  Dot
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Data {
+  Events,
+  Timeline
+}
+
+#[derive(Debug)]
+enum Command {
+  Load,
+  Peak,
+  AliveOn(String),
+  Export
+}
+
+// the schema as given by the column options
+struct Columns {
+  name: Column,
+  birth: DateColumn,
+  birth_place: Option<Column>,
+  death: Option<DateColumn>,
+  death_place: Option<Column>,
+  attributes: Vec<(String, Column)>,
+  delimiter: u8
+}
+
+impl Default for Columns {
+  fn default() -> Columns {
+    Columns {
+      name: Column::Index(0),
+      birth: DateColumn::new(Column::Index(1)),
+      birth_place: Some(Column::Index(2)),
+      death: Some(DateColumn::new(Column::Index(3))),
+      death_place: Some(Column::Index(4)),
+      attributes: Vec::new(),
+      delimiter: b','
+    }
+  }
+}
+
+impl Columns {
+  fn schema(&self, parser: DateParser) -> Schema {
+    let mut schema = Schema::new(self.name.clone(), with_parser(self.birth.clone(), parser)).delimiter(self.delimiter);
+
+    if let Some(ref death) = self.death {
+      schema = schema.end(with_parser(death.clone(), parser));
+    }
+
+    let places = [("birth place", &self.birth_place), ("death place", &self.death_place)];
+
+    for &(name, column) in places.iter() {
+      if let Some(ref column) = *column {
+        schema = schema.attribute(name, column.clone());
+      }
+    }
+
+    for (name, column) in &self.attributes {
+      schema = schema.attribute(name, column.clone());
+    }
+
+    schema
+  }
+}
+
+struct Options {
+  command: Command,
+  file: String,
+  columns: Columns,
+  schema_file: Option<String>,
+  date_parser: DateParser,
+  order: SameDayOrder,
+  format: Format,
+  data: Data,
+  output: Option<String>,
+  budget: Option<usize>
+}
+
+fn parse_optional<T: FromStr<Err = String>>(value: &str) -> Result<Option<T>, String> {
+  if value.trim() == "-" {
+    Ok(None)
+  } else {
+    value.parse().map(Some)
+  }
+}
+
+fn parse_attribute(value: &str) -> Result<(String, Column), String> {
+  match value.find('=') {
+    Some(pos) => Ok((value[..pos].trim().to_string(), value[pos + 1..].parse()?)),
+    None => Err(format!("expected NAME=COL, got {}", value))
+  }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+  let mut args = args.iter();
+
+  let command = match args.next().map(|arg| arg.as_str()) {
+    Some("load") => Command::Load,
+    Some("peak") => Command::Peak,
+    Some("alive-on") => Command::AliveOn(args.next().ok_or("alive-on needs a date")?.clone()),
+    Some("export") => Command::Export,
+    Some(other) => return Err(format!("unknown command: {}", other)),
+    None => return Err("no command given".to_string())
+  };
+
+  let mut file = None;
+  let mut columns = Columns::default();
+  let mut schema_file = None;
+  let mut date_parser = DateParser::new();
+  let mut order = SameDayOrder::BirthsFirst;
+  let mut format = Format::Csv;
+  let mut data = Data::Timeline;
+  let mut output = None;
+  let mut budget = None;
+  // a schema file describes the columns and their date formats by itself
+  let mut schema_conflict = None;
+
+  while let Some(arg) = args.next() {
+    if !arg.starts_with("--") {
+      if file.is_some() {
+        return Err(format!("unexpected argument: {}", arg));
+      }
+      file = Some(arg.clone());
+      continue;
+    }
+
+    match arg.as_str() {
+      "--name" | "--birth" | "--birth-place" | "--death" | "--death-place" |
+      "--attribute" | "--delimiter" | "--day-first" | "--old-style" => schema_conflict = Some(arg),
+      _ => {}
+    }
+
+    match arg.as_str() {
+      "--day-first" => {
+        date_parser = date_parser.numeric_order(NumericOrder::DayFirst);
+        continue;
+      },
+      "--old-style" => {
+        date_parser = date_parser.old_style_before(british_switch());
+        continue;
+      },
+      "--deaths-first" => {
+        order = SameDayOrder::DeathsFirst;
+        continue;
+      },
+      _ => {}
+    }
+
+    let value = args.next().ok_or(format!("{} needs a value", arg))?;
+
+    match arg.as_str() {
+      "--name" => columns.name = value.parse()?,
+      "--birth" => columns.birth = value.parse()?,
+      "--birth-place" => columns.birth_place = parse_optional(value)?,
+      "--death" => columns.death = parse_optional(value)?,
+      "--death-place" => columns.death_place = parse_optional(value)?,
+      "--attribute" => columns.attributes.push(parse_attribute(value)?),
+      "--delimiter" => columns.delimiter = parse_delimiter(value)?,
+      "--schema" => schema_file = Some(value.clone()),
+      "--format" => format = match value.as_str() {
+        "csv" => Format::Csv,
+        "json" => Format::Json,
+        "svg" => Format::Svg,
+        "dot" => Format::Dot,
+        _ => return Err(format!("unknown format: {}", value))
+      },
+      "--what" => data = match value.as_str() {
+        "events" => Data::Events,
+        "timeline" => Data::Timeline,
+        _ => return Err(format!("can only export events or timeline, not {}", value))
+      },
+      "--output" => output = Some(value.clone()),
+      "--budget" => budget = Some(value.parse().map_err(|_| format!("not a number of events: {}", value))?),
+      _ => return Err(format!("unknown option: {}", arg))
+    }
+  }
+
+  let streamable = match command {
+    Command::Export => (format, data) == (Format::Csv, Data::Timeline),
+    _ => false
+  };
+
+  if budget.is_some() && !streamable {
+    return Err("--budget only works with export --what timeline --format csv".to_string());
+  }
+
+  if let (Some(_), Some(flag)) = (&schema_file, schema_conflict) {
+    return Err(format!("{} can't be combined with --schema", flag));
+  }
+
+  Ok(Options {
+    command,
+    file: file.ok_or("no file given")?,
+    columns,
+    schema_file,
+    date_parser,
+    order,
+    format,
+    data,
+    output,
+    budget
+  })
+}
+
+fn label(record: &Record) -> String {
+  record.get_label().to_string()
+}
+
+// the parser given by --day-first and --old-style applies to the date columns
+// that don't name a format
+fn with_parser(column: DateColumn, parser: DateParser) -> DateColumn {
+  match column.format {
+    DateFormat::Lenient(_) => DateColumn { format: DateFormat::Lenient(parser), ..column },
+    _ => column
+  }
+}
+
+fn schema(options: &Options) -> Result<Schema, String> {
+  match options.schema_file {
+    Some(ref path) => {
+      let mut text = String::new();
+
+      File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut text))
+        .map_err(|e| format!("{}: {}", path, e))?;
+
+      text.parse().map_err(|e| format!("{}: {}", path, e))
+    },
+    None => Ok(options.columns.schema(options.date_parser))
+  }
+}
+
+fn print_errors(report: &RecordReport) {
+  for error in &report.errors {
+    eprintln!("{}", error);
+  }
+}
+
+fn load(report: &RecordReport) -> Result<(), String> {
+  println!("{} rows read, {} rejected", report.records.len() + report.errors.len(), report.errors.len());
+
+  if report.errors.is_empty() {
+    Ok(())
+  } else {
+    Err(format!("{} invalid rows", report.errors.len()))
+  }
+}
+
+fn peak(report: &RecordReport, order: SameDayOrder) {
+  for interval in peak_intervals(&report.records, order) {
+    let until = match interval.last_day() {
+      Some(day) => day.format("%Y-%m-%d").to_string(),
+      None => "now".to_string()
+    };
+
+    let labels: Vec<&str> = interval.alive.iter().map(|record| record.get_label()).collect();
+
+    println!("{} to {}, {} alive: {}", interval.start.format("%Y-%m-%d"), until, interval.count(), labels.join(", "));
+  }
+}
+
+fn alive_on(report: &RecordReport, options: &Options, date: &str) -> Result<(), String> {
+  let date = options.date_parser.parse(date)?.ok_or("alive-on needs a date")?;
+  let index = IntervalIndex::new(&report.records, options.order);
+
+  for record in index.alive_on(date.date) {
+    println!("{} ({})", record.get_label(), record.get_start());
+  }
+
+  Ok(())
+}
+
+fn export(report: &RecordReport, options: &Options) -> Result<(), String> {
+  let records = &report.records;
+
+  let text = match (options.format, options.data) {
+    (Format::Csv, Data::Events) => events_to_csv(&create_sorted_events(records), label)?,
+    (Format::Csv, Data::Timeline) => timeline_to_csv(&alive_timeline(records))?,
+    (Format::Json, Data::Events) => events_to_json(&create_sorted_events(records), label).pretty().to_string(),
+    (Format::Json, Data::Timeline) => timeline_to_json(&alive_timeline(records)).pretty().to_string(),
+    (Format::Svg, _) => GanttChart::new().render(records, label),
+    (Format::Dot, _) => OverlapGraph::new(records, options.order, UTC::now()).to_dot(label)
+  };
+
+  match options.output {
+    Some(ref path) => File::create(path)
+      .and_then(|mut file| file.write_all(text.as_bytes()))
+      .map_err(|e| format!("{}: {}", path, e)),
+    None => {
+      print!("{}", text);
+      Ok(())
+    }
+  }
+}
+
+// reads FILE one row at a time, the timeline is written as it's merged
+fn export_streamed(schema: &Schema, options: &Options, budget: usize) -> Result<(), String> {
+  let rows = stream_csv_schema(&options.file, schema).map_err(|e| format!("{}: {}", options.file, e))?;
+  let records = rows.filter_map(|row| row.map_err(|error| eprintln!("{}", error)).ok());
+
+  let timeline = external_alive_timeline(records, &SweepConfig::new().budget(budget))?;
+
+  match options.output {
+    Some(ref path) => File::create(path)
+      .map_err(|e| format!("{}: {}", path, e))
+      .and_then(|file| write_timeline_csv(timeline, BufWriter::new(file))),
+    None => write_timeline_csv(timeline, std::io::stdout().lock())
+  }.map(|_| ())
+}
+
+fn run(options: &Options) -> Result<(), String> {
+  let schema = schema(options)?;
+
+  if let Some(budget) = options.budget {
+    return export_streamed(&schema, options, budget);
+  }
+  let report = read_csv_schema(&options.file, &schema).map_err(|e| format!("{}: {}", options.file, e))?;
+
+  // everything but load goes on with the rows that could be read
+  print_errors(&report);
+
+  match options.command {
+    Command::Load => load(&report),
+    Command::Peak => {
+      peak(&report, options.order);
+      Ok(())
+    },
+    Command::AliveOn(ref date) => alive_on(&report, options, date),
+    Command::Export => export(&report, options)
+  }
+}
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+
+  let options = match parse_args(&args) {
+    Ok(options) => options,
+    Err(message) => {
+      eprintln!("{}\n\n{}", message, USAGE);
+      process::exit(2);
+    }
+  };
+
+  if let Err(message) = run(&options) {
+    eprintln!("{}", message);
+    process::exit(1);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(|arg| arg.to_string()).collect()
+  }
+
+  #[test]
+  fn test_parse_args() {
+    let options = parse_args(&args("export --format json --what events --name who --death - --deaths-first people.csv")).unwrap();
+
+    assert_eq!("people.csv", options.file);
+    assert_eq!((Format::Json, Data::Events), (options.format, options.data));
+    assert_eq!(SameDayOrder::DeathsFirst, options.order);
+    assert_eq!(Column::Header("who".to_string()), options.columns.name);
+    assert!(options.columns.death.is_none());
+    assert_eq!(None, options.budget);
+    assert_eq!(Some(Column::Index(4)), options.columns.death_place);
+
+    let options = parse_args(&args("peak --birth born|%d.%m.%Y --attribute flat=3 --attribute state=birth_state x.csv")).unwrap();
+    let schema = options.columns.schema(options.date_parser);
+
+    assert_eq!(Column::Header("born".to_string()), options.columns.birth.column);
+    assert_eq!(vec!["birth place", "death place", "flat", "state"], schema.get_attribute_names());
+
+    match parse_args(&args("alive-on 1826-07-04 --delimiter ; x.csv")).unwrap() {
+      Options { command: Command::AliveOn(date), columns, .. } => {
+        assert_eq!("1826-07-04", date);
+        assert_eq!(b';', columns.delimiter);
+      },
+      _ => panic!("expected alive-on")
+    }
+  }
+
+  #[test]
+  fn test_parse_args_errors() {
+    assert!(parse_args(&args("")).is_err());
+    assert!(parse_args(&args("peak")).is_err());
+    assert!(parse_args(&args("peak a.csv b.csv")).is_err());
+    assert!(parse_args(&args("alive-on")).is_err());
+    assert!(parse_args(&args("export --format xml a.csv")).is_err());
+    assert!(parse_args(&args("load --name")).is_err());
+    assert!(parse_args(&args("load --delimiter ;; a.csv")).is_err());
+    assert!(parse_args(&args("load --attribute flat a.csv")).is_err());
+    assert!(parse_args(&args("load --birth born|yyyy a.csv")).is_err());
+    assert!(parse_args(&args("export --budget many a.csv")).is_err());
+    assert!(parse_args(&args("export --budget 1000 --format json a.csv")).is_err());
+    assert!(parse_args(&args("peak --budget 1000 a.csv")).is_err());
+
+    assert_eq!(Some("--name can't be combined with --schema".to_string()), parse_args(&args("load --schema s.txt --name who a.csv")).err());
+    assert!(parse_args(&args("load --day-first --schema s.txt a.csv")).is_err());
+    assert!(parse_args(&args("load --schema s.txt --deaths-first a.csv")).is_ok());
+  }
+
+}
@@ -0,0 +1,250 @@
+// overlap.rs <weldale@gmail.com>
+//
+// Day precise sweep over lifespans. Every lifespan becomes a half-open
+// interval of days, whether the day of the end still counts is decided by
+// `SameDayOrder`.
+
+use chrono::{
+  DateTime,
+  Duration,
+  UTC
+};
+
+use std::collections::BTreeSet;
+
+use Lifespan;
+
+// what happens when one lifespan ends on the day another one starts
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SameDayOrder {
+  // the day of the end still counts, both overlap on that day
+  BirthsFirst,
+  // the end comes first, the two never overlap
+  DeathsFirst
+}
+
+#[derive(Debug)]
+pub struct PeakInterval<'a, T: 'a> {
+  pub start: DateTime<UTC>,
+  // first day that isn't part of the interval anymore, None if it's still ongoing
+  pub end: Option<DateTime<UTC>>,
+  pub alive: Vec<&'a T>
+}
+
+impl<'a, T: 'a> PeakInterval<'a, T> {
+  pub fn count(&self) -> usize {
+    self.alive.len()
+  }
+
+  pub fn last_day(&self) -> Option<DateTime<UTC>> {
+    self.end.map(|end| end - Duration::days(1))
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Change {
+  // ends sort first so a lifespan can take over on the day another one stops
+  End,
+  Start
+}
+
+// the half-open interval of days a record is alive, None if it never is
+// because it's empty or reversed
+pub fn day_interval<T: Lifespan>(record: &T, order: SameDayOrder) -> Option<(DateTime<UTC>, Option<DateTime<UTC>>)> {
+  let start = record.start();
+  let end = record.end().map(|end| match order {
+    SameDayOrder::BirthsFirst => end + Duration::days(1),
+    SameDayOrder::DeathsFirst => end
+  });
+
+  if end.is_some_and(|end| end <= start) {
+    return None;
+  }
+
+  Some((start, end))
+}
+
+// (date, change, record index), sorted by date
+fn sweep_events<T: Lifespan>(records: &[T], order: SameDayOrder) -> Vec<(DateTime<UTC>, Change, usize)> {
+  let mut events = Vec::new();
+
+  for (index, record) in records.iter().enumerate() {
+    let (start, end) = match day_interval(record, order) {
+      Some(interval) => interval,
+      None => continue
+    };
+
+    events.push((start, Change::Start, index));
+
+    if let Some(end) = end {
+      events.push((end, Change::End, index));
+    }
+  }
+
+  events.sort();
+  events
+}
+
+// every stretch of days during which the most records are alive at once,
+// in chronological order
+pub fn peak_intervals<T: Lifespan>(records: &[T], order: SameDayOrder) -> Vec<PeakInterval<'_, T>> {
+  let events = sweep_events(records, order);
+
+  let mut max = 0;
+  let mut count = 0;
+
+  for (i, &(date, change, _)) in events.iter().enumerate() {
+    match change {
+      Change::Start => count += 1,
+      Change::End => count -= 1
+    }
+
+    // only the count after the last event of a day lasts
+    if events.get(i + 1).is_none_or(|next| next.0 != date) {
+      max = std::cmp::max(max, count);
+    }
+  }
+
+  let mut result = Vec::new();
+
+  if max == 0 {
+    return result;
+  }
+
+  let mut alive = BTreeSet::new();
+
+  for (i, &(date, change, index)) in events.iter().enumerate() {
+    match change {
+      Change::Start => alive.insert(index),
+      Change::End => alive.remove(&index)
+    };
+
+    let next = events.get(i + 1);
+
+    if next.is_some_and(|next| next.0 == date) || alive.len() != max {
+      continue;
+    }
+
+    result.push(PeakInterval {
+      start: date,
+      end: next.map(|next| next.0),
+      alive: alive.iter().map(|&index| &records[index]).collect()
+    });
+  }
+
+  result
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  use chrono;
+  use chrono::offset::TimeZone;
+
+  use read_csv;
+
+  struct Span {
+    name: &'static str,
+    from: DateTime<UTC>,
+    until: Option<DateTime<UTC>>
+  }
+
+  impl Lifespan for Span {
+    fn start(&self) -> DateTime<UTC> {
+      self.from
+    }
+
+    fn end(&self) -> Option<DateTime<UTC>> {
+      self.until
+    }
+  }
+
+  fn day(d: u32) -> DateTime<UTC> {
+    chrono::UTC.ymd(2016, 3, d).and_hms(0, 0, 0)
+  }
+
+  fn span(name: &'static str, from: u32, until: Option<u32>) -> Span {
+    Span { name, from: day(from), until: until.map(day) }
+  }
+
+  fn names(interval: &PeakInterval<Span>) -> Vec<&'static str> {
+    interval.alive.iter().map(|s| s.name).collect()
+  }
+
+  #[test]
+  fn test_same_day_order() {
+    let spans = vec![span("a", 1, Some(5)), span("b", 5, Some(8))];
+
+    let peaks = peak_intervals(&spans, SameDayOrder::BirthsFirst);
+    assert_eq!(1, peaks.len());
+    assert_eq!(day(5), peaks[0].start);
+    assert_eq!(Some(day(5)), peaks[0].last_day());
+    assert_eq!(vec!["a", "b"], names(&peaks[0]));
+
+    let peaks = peak_intervals(&spans, SameDayOrder::DeathsFirst);
+    assert_eq!(2, peaks.len());
+    assert_eq!((day(1), Some(day(5))), (peaks[0].start, peaks[0].end));
+    assert_eq!((day(5), Some(day(8))), (peaks[1].start, peaks[1].end));
+    assert_eq!(vec!["b"], names(&peaks[1]));
+  }
+
+  #[test]
+  fn test_peak_intervals() {
+    let spans = vec![
+      span("a", 1, Some(10)),
+      span("b", 3, Some(4)),
+      span("c", 6, None),
+      span("d", 8, Some(8)),
+      span("e", 20, None)
+    ];
+
+    let peaks = peak_intervals(&spans, SameDayOrder::DeathsFirst);
+
+    assert_eq!(3, peaks.len());
+    assert!(peaks.iter().all(|peak| peak.count() == 2));
+    assert_eq!(vec!["a", "b"], names(&peaks[0]));
+    assert_eq!((day(6), Some(day(10))), (peaks[1].start, peaks[1].end));
+    assert_eq!((day(20), None), (peaks[2].start, peaks[2].end));
+    assert_eq!(vec!["c", "e"], names(&peaks[2]));
+
+    // the one day span of d only counts when its end day is included
+    let peaks = peak_intervals(&spans, SameDayOrder::BirthsFirst);
+
+    assert_eq!(1, peaks.len());
+    assert_eq!((day(8), Some(day(9))), (peaks[0].start, peaks[0].end));
+    assert_eq!(vec!["a", "c", "d"], names(&peaks[0]));
+  }
+
+  #[test]
+  fn test_unsorted_and_empty() {
+    let spans: Vec<Span> = Vec::new();
+    assert!(peak_intervals(&spans, SameDayOrder::BirthsFirst).is_empty());
+
+    let spans = vec![span("late", 9, None), span("reversed", 5, Some(2)), span("early", 1, Some(3))];
+    let peaks = peak_intervals(&spans, SameDayOrder::DeathsFirst);
+
+    assert_eq!(2, peaks.len());
+    assert_eq!(vec!["early"], names(&peaks[0]));
+    assert_eq!(vec!["late"], names(&peaks[1]));
+  }
+
+  #[test]
+  fn test_presidents() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+    let peaks = peak_intervals(&presidents, SameDayOrder::BirthsFirst);
+
+    assert!(!peaks.is_empty());
+
+    for peak in &peaks {
+      let last_day = peak.last_day().unwrap_or(peak.start);
+
+      for president in &peak.alive {
+        assert!(president.start() <= peak.start);
+        assert!(president.end().is_none_or(|end| end >= last_day));
+      }
+    }
+  }
+
+}
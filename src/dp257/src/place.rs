@@ -0,0 +1,441 @@
+// place.rs <weldale@gmail.com>
+//
+// Splits place names like "Westmoreland Co. Va." or "Springfield, Sangamon
+// County, Illinois" into locality, county, state and country, and counts
+// births and deaths per region.
+
+use std::collections::{
+  BTreeMap,
+  HashMap,
+  HashSet
+};
+use std::fmt;
+
+const UNITED_STATES: &str = "United States";
+
+// every state with its postal code and the older abbreviations found in
+// print, like Va. or New Hamp.
+const STATES: [(&str, &[&str]); 51] = [
+  ("Alabama", &["AL", "Ala."]),
+  ("Alaska", &["AK"]),
+  ("Arizona", &["AZ", "Ariz."]),
+  ("Arkansas", &["AR", "Ark."]),
+  ("California", &["CA", "Cal.", "Calif."]),
+  ("Colorado", &["CO", "Colo."]),
+  ("Connecticut", &["CT", "Conn."]),
+  ("Delaware", &["DE", "Del."]),
+  ("District of Columbia", &["DC", "D.C."]),
+  ("Florida", &["FL", "Fla."]),
+  ("Georgia", &["GA", "Ga."]),
+  ("Hawaii", &["HI"]),
+  ("Idaho", &["ID"]),
+  ("Illinois", &["IL", "Ill."]),
+  ("Indiana", &["IN", "Ind."]),
+  ("Iowa", &["IA"]),
+  ("Kansas", &["KS", "Kan.", "Kans."]),
+  ("Kentucky", &["KY", "Ky."]),
+  ("Louisiana", &["LA", "La."]),
+  ("Maine", &["ME"]),
+  ("Maryland", &["MD", "Md."]),
+  ("Massachusetts", &["MA", "Mass."]),
+  ("Michigan", &["MI", "Mich."]),
+  ("Minnesota", &["MN", "Minn."]),
+  ("Mississippi", &["MS", "Miss."]),
+  ("Missouri", &["MO", "Mo."]),
+  ("Montana", &["MT", "Mont."]),
+  ("Nebraska", &["NE", "Neb.", "Nebr."]),
+  ("Nevada", &["NV", "Nev."]),
+  ("New Hampshire", &["NH", "N.H.", "New Hamp."]),
+  ("New Jersey", &["NJ", "N.J."]),
+  ("New Mexico", &["NM", "N.M.", "N.Mex."]),
+  ("New York", &["NY", "N.Y."]),
+  ("North Carolina", &["NC", "N.C."]),
+  ("North Dakota", &["ND", "N.D.", "N.Dak."]),
+  ("Ohio", &["OH"]),
+  ("Oklahoma", &["OK", "Okla."]),
+  ("Oregon", &["OR", "Ore.", "Oreg."]),
+  ("Pennsylvania", &["PA", "Pa.", "Penn."]),
+  ("Rhode Island", &["RI", "R.I."]),
+  ("South Carolina", &["SC", "S.C."]),
+  ("South Dakota", &["SD", "S.D.", "S.Dak."]),
+  ("Tennessee", &["TN", "Tenn."]),
+  ("Texas", &["TX", "Tex."]),
+  ("Utah", &["UT"]),
+  ("Vermont", &["VT", "Vt."]),
+  ("Virginia", &["VA", "Va."]),
+  ("Washington", &["WA", "Wash."]),
+  ("West Virginia", &["WV", "W.Va."]),
+  ("Wisconsin", &["WI", "Wis.", "Wisc."]),
+  ("Wyoming", &["WY", "Wyo."])
+];
+
+// counties whose names have more than one word, the others are taken to be
+// the last word before "Co." when a locality comes first
+const MULTI_WORD_COUNTIES: [&str; 12] = [
+  "Charles City", "James City", "King and Queen", "King George", "King William",
+  "New Castle", "New Kent", "Prince Edward", "Prince George", "Prince George's",
+  "Prince William", "St. Mary's"
+];
+
+const COUNTRY_ALIASES: [&str; 4] = ["USA", "US", "United States", "United States of America"];
+
+// the longest alias, "United States of America"
+const MAX_ALIAS_WORDS: usize = 4;
+
+// case, dots and spaces don't matter: "N. C.", "n.c." and "NC" are the same
+fn alias_key(s: &str) -> String {
+  s.chars().filter(|c| c.is_alphanumeric()).flat_map(|c| c.to_lowercase()).collect()
+}
+
+fn is_county(word: &str) -> bool {
+  let key = alias_key(word);
+  key == "co" || key == "county"
+}
+
+// bare two letter words like "in", "or" or "me" only count as codes when
+// written in capitals
+fn is_lowercase_code(words: &[&str]) -> bool {
+  let text = words.concat();
+  alias_key(&text).len() == 2 && !text.contains('.') && text.chars().any(char::is_lowercase)
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Place {
+  pub locality: Option<String>,
+  // without the "County"
+  pub county: Option<String>,
+  pub state: Option<String>,
+  pub country: Option<String>
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Level {
+  Locality,
+  County,
+  State,
+  Country
+}
+
+impl Place {
+  // the name of the region the place lies in, localities and counties come
+  // with their state because the same names turn up in many of them
+  pub fn region(&self, level: Level) -> Option<String> {
+    let qualified = |name: &Option<String>| name.as_ref().map(|name| match self.state {
+      Some(ref state) => format!("{}, {}", name, state),
+      None => name.clone()
+    });
+
+    match level {
+      Level::Locality => qualified(&self.locality),
+      Level::County => qualified(&self.county.as_ref().map(|county| format!("{} County", county))),
+      Level::State => self.state.clone(),
+      Level::Country => self.country.clone()
+    }
+  }
+}
+
+impl fmt::Display for Place {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let county = self.county.as_ref().map(|county| format!("{} County", county));
+    let parts: Vec<&str> = [&self.locality, &county, &self.state, &self.country].iter()
+      .filter_map(|part| part.as_deref())
+      .collect();
+
+    write!(f, "{}", parts.join(", "))
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct PlaceParser {
+  states: HashMap<String, String>,
+  countries: HashMap<String, String>,
+  counties: HashSet<String>
+}
+
+impl PlaceParser {
+  pub fn new() -> PlaceParser {
+    let mut parser = PlaceParser {
+      states: HashMap::new(),
+      countries: HashMap::new(),
+      counties: HashSet::new()
+    };
+
+    for &(state, aliases) in STATES.iter() {
+      parser = parser.state_alias(state, state);
+
+      for alias in aliases {
+        parser = parser.state_alias(alias, state);
+      }
+    }
+
+    for alias in COUNTRY_ALIASES.iter() {
+      parser = parser.country_alias(alias, UNITED_STATES);
+    }
+
+    for county in MULTI_WORD_COUNTIES.iter() {
+      parser = parser.county_name(county);
+    }
+
+    parser
+  }
+
+  pub fn state_alias(mut self, alias: &str, state: &str) -> PlaceParser {
+    self.states.insert(alias_key(alias), state.to_string());
+    self
+  }
+
+  pub fn country_alias(mut self, alias: &str, country: &str) -> PlaceParser {
+    self.countries.insert(alias_key(alias), country.to_string());
+    self
+  }
+
+  // a county name of several words that shouldn't be split into a locality
+  // and a county
+  pub fn county_name(mut self, name: &str) -> PlaceParser {
+    self.counties.insert(alias_key(name));
+    self
+  }
+
+  // the name and the number of words of the longest alias at the end of `words`
+  fn match_suffix(aliases: &HashMap<String, String>, words: &[&str]) -> Option<(String, usize)> {
+    (1..=std::cmp::min(MAX_ALIAS_WORDS, words.len())).rev()
+      .filter(|&n| !is_lowercase_code(&words[words.len() - n..]))
+      .find_map(|n| aliases.get(&alias_key(&words[words.len() - n..].concat())).map(|name| (name.clone(), n)))
+  }
+
+  // where the county name starts in the words before "Co."
+  fn county_start(&self, words: &[&str]) -> usize {
+    (0..words.len() - 1)
+      .find(|&start| self.counties.contains(&alias_key(&words[start..].concat())))
+      .unwrap_or(words.len() - 1)
+  }
+
+  // None for a blank string
+  pub fn parse(&self, s: &str) -> Option<Place> {
+    let mut parts: Vec<Vec<&str>> = s.split(',')
+      .map(|part| part.split_whitespace().collect::<Vec<&str>>())
+      .filter(|part| !part.is_empty())
+      .collect();
+
+    if parts.is_empty() {
+      return None;
+    }
+
+    let mut place = Place::default();
+
+    // the country and the state are taken off the end, either as whole
+    // parts or as the last words of one
+    let mut last = parts.pop().unwrap();
+
+    if let Some((country, n)) = PlaceParser::match_suffix(&self.countries, &last) {
+      place.country = Some(country);
+      last.truncate(last.len() - n);
+    }
+
+    if last.is_empty() {
+      last = match parts.pop() {
+        Some(part) => part,
+        None => return Some(place)
+      };
+    }
+
+    // "Westmoreland Co." is a county, not Colorado; "CO" on its own is
+    let ends_in_county = last.last().is_some_and(|&word| is_county(word) && word != "CO");
+
+    let state = if ends_in_county { None } else { PlaceParser::match_suffix(&self.states, &last) };
+
+    if let Some((state, n)) = state {
+      place.state = Some(state);
+      place.country = place.country.or(Some(UNITED_STATES.to_string()));
+      last.truncate(last.len() - n);
+    } else if !parts.is_empty() && place.country.is_none() {
+      place.country = Some(last.join(" "));
+      last.clear();
+    }
+
+    if !last.is_empty() {
+      parts.push(last);
+    }
+
+    // "Westmoreland Co." and "Orange County" are counties, what's left a
+    // locality. Without a part of its own the locality comes first, as in
+    // "Hyde Park Dutchess Co."
+    let mut localities = Vec::new();
+
+    for (index, part) in parts.iter().enumerate() {
+      match part.iter().position(|word| is_county(word)) {
+        Some(pos) if pos > 0 && place.county.is_none() => {
+          let start = if index == 0 { self.county_start(&part[..pos]) } else { 0 };
+
+          if start > 0 {
+            localities.push(part[..start].join(" "));
+          }
+
+          place.county = Some(part[start..pos].join(" "));
+
+          if pos + 1 < part.len() {
+            localities.push(part[pos + 1..].join(" "));
+          }
+        },
+        _ => localities.push(part.join(" "))
+      }
+    }
+
+    if !localities.is_empty() {
+      place.locality = Some(localities.join(", "));
+    }
+
+    Some(place)
+  }
+}
+
+impl Default for PlaceParser {
+  fn default() -> PlaceParser {
+    PlaceParser::new()
+  }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegionCount {
+  pub born: usize,
+  pub died: usize,
+  // died in the region they were born in
+  pub stayed: usize
+}
+
+// births and deaths per region at `level`, places that don't reach that
+// level aren't counted
+pub fn count_by_region<T, B, D>(records: &[T], parser: &PlaceParser, level: Level, birth_place: B, death_place: D) -> BTreeMap<String, RegionCount>
+where B: Fn(&T) -> Option<&str>, D: Fn(&T) -> Option<&str> {
+  let mut counts: BTreeMap<String, RegionCount> = BTreeMap::new();
+
+  let region = |place: Option<&str>| place.and_then(|place| parser.parse(place)).and_then(|place| place.region(level));
+
+  for record in records {
+    let born = region(birth_place(record));
+    let died = region(death_place(record));
+
+    if let Some(ref born) = born {
+      counts.entry(born.clone()).or_default().born += 1;
+    }
+
+    if let Some(ref died) = died {
+      let count = counts.entry(died.clone()).or_default();
+      count.died += 1;
+
+      if born.as_ref() == Some(died) {
+        count.stayed += 1;
+      }
+    }
+  }
+
+  counts
+}
+
+// the records that died in the region they were born in
+pub fn died_where_born<'a, T, B, D>(records: &'a [T], parser: &PlaceParser, level: Level, birth_place: B, death_place: D) -> Vec<&'a T>
+where B: Fn(&T) -> Option<&str>, D: Fn(&T) -> Option<&str> {
+  let region = |place: Option<&str>| place.and_then(|place| parser.parse(place)).and_then(|place| place.region(level));
+
+  records.iter()
+    .filter(|record| {
+      let born = region(birth_place(record));
+      born.is_some() && born == region(death_place(record))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  use President;
+  use read_csv;
+
+  fn place(locality: Option<&str>, county: Option<&str>, state: Option<&str>, country: Option<&str>) -> Option<Place> {
+    Some(Place {
+      locality: locality.map(|s| s.to_string()),
+      county: county.map(|s| s.to_string()),
+      state: state.map(|s| s.to_string()),
+      country: country.map(|s| s.to_string())
+    })
+  }
+
+  #[test]
+  fn test_parse() {
+    let parser = PlaceParser::new();
+    let us = Some(UNITED_STATES);
+
+    assert_eq!(place(None, Some("Westmoreland"), Some("Virginia"), us), parser.parse("Westmoreland Co. Va."));
+    assert_eq!(place(None, Some("Westmoreland"), Some("Virginia"), us), parser.parse("Westmoreland Co., Virginia"));
+    assert_eq!(place(Some("New York"), None, Some("New York"), us), parser.parse("New York New York"));
+    assert_eq!(place(Some("Washington"), None, Some("District of Columbia"), us), parser.parse("Washington D.C"));
+    assert_eq!(place(Some("Concord"), None, Some("New Hampshire"), us), parser.parse("Concord New Hamp."));
+    assert_eq!(place(Some("Springfield"), Some("Sangamon"), Some("Illinois"), us), parser.parse("Springfield, Sangamon County, Illinois, USA"));
+    assert_eq!(place(Some("Honolulu"), None, Some("Hawaii"), us), parser.parse("Honolulu HI"));
+    assert_eq!(place(Some("London"), None, None, Some("England")), parser.parse("London, England"));
+    assert_eq!(place(Some("Somewhere"), None, None, None), parser.parse("Somewhere"));
+    assert_eq!(None, parser.parse("  "));
+
+    // "Co." is no postal code, and neither are ordinary words
+    assert_eq!(place(None, Some("Westmoreland"), None, None), parser.parse("Westmoreland Co."));
+    assert_eq!(place(None, Some("Orange"), None, None), parser.parse("Orange Co"));
+    assert_eq!(place(Some("Denver"), None, Some("Colorado"), us), parser.parse("Denver CO"));
+    assert_eq!(place(Some("Portland or Salem"), None, None, None), parser.parse("Portland or Salem"));
+    assert_eq!(place(Some("Born in"), None, None, None), parser.parse("Born in"));
+    assert_eq!(place(Some("Lafayette"), None, Some("Louisiana"), us), parser.parse("Lafayette La."));
+
+    assert_eq!(place(Some("Hyde Park"), Some("Dutchess"), Some("New York"), us), parser.parse("Hyde Park Dutchess Co. N.Y."));
+    assert_eq!(place(None, Some("Charles City"), Some("Virginia"), us), parser.parse("Charles City Co. Va."));
+    assert_eq!(place(Some("Kinderhook"), Some("Columbia"), Some("New York"), us), parser.parse("Kinderhook, Columbia Co., NY"));
+    assert_eq!(place(Some("Springfield"), Some("Los Angeles"), Some("California"), us), parser.parse("Springfield, Los Angeles County, California"));
+
+    let custom = PlaceParser::new().state_alias("Virg.", "Virginia").country_alias("UK", "United Kingdom").county_name("Los Angeles");
+    assert_eq!(place(None, Some("Los Angeles"), Some("California"), us), custom.parse("Los Angeles County Cal."));
+    assert_eq!(Some("Virginia".to_string()), custom.parse("Richmond Virg.").unwrap().state);
+    assert_eq!(Some("United Kingdom".to_string()), custom.parse("London UK").unwrap().country);
+  }
+
+  #[test]
+  fn test_region() {
+    let place = PlaceParser::new().parse("Westmoreland Co. Va.").unwrap();
+
+    assert_eq!("Westmoreland County, Virginia, United States", place.to_string());
+    assert_eq!(Some("Westmoreland County, Virginia".to_string()), place.region(Level::County));
+    assert_eq!(Some("Virginia".to_string()), place.region(Level::State));
+    assert_eq!(None, place.region(Level::Locality));
+  }
+
+  fn birth_place(p: &President) -> Option<&str> {
+    Some(p.get_birth_place())
+  }
+
+  #[test]
+  fn test_presidents() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+    let parser = PlaceParser::new();
+
+    for president in &presidents {
+      let place = parser.parse(president.get_birth_place()).unwrap();
+      assert!(place.state.is_some(), "{}", president.get_birth_place());
+    }
+
+    let counts = count_by_region(&presidents, &parser, Level::State, birth_place, President::get_death_place);
+
+    assert_eq!(8, counts["Virginia"].born);
+    assert_eq!(7, counts["District of Columbia"].died);
+    assert_eq!(0, counts["District of Columbia"].born);
+    assert_eq!(presidents.len(), counts.values().map(|count| count.born).sum::<usize>());
+
+    let stayed = died_where_born(&presidents, &parser, Level::State, birth_place, President::get_death_place);
+    assert_eq!(counts.values().map(|count| count.stayed).sum::<usize>(), stayed.len());
+    assert!(stayed.iter().any(|p| p.get_name() == "Thomas Jefferson"));
+    assert!(!stayed.iter().any(|p| p.get_name() == "John Quincy Adams"));
+
+    let by_county = died_where_born(&presidents, &parser, Level::County, birth_place, President::get_death_place);
+    let names: Vec<&str> = by_county.iter().map(|p| p.get_name()).collect();
+    assert_eq!(vec!["Thomas Jefferson", "Lyndon B. Johnson"], names);
+  }
+
+}
@@ -0,0 +1,381 @@
+// stats.rs <weldale@gmail.com>
+//
+// Ages and lifespan statistics. Ages are counted in whole calendar months
+// and the days left over; a lifespan that hasn't ended counts up to a
+// reference date.
+
+use chrono::{
+  DateTime,
+  Datelike,
+  NaiveDate,
+  UTC
+};
+
+use std::collections::{
+  BTreeMap,
+  BTreeSet
+};
+use std::fmt;
+
+use {
+  EventType,
+  Lifespan,
+  is_reversed
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Age {
+  pub years: u32,
+  pub months: u32,
+  pub days: u32
+}
+
+impl fmt::Display for Age {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} years, {} months, {} days", self.years, self.months, self.days)
+  }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+  let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+
+  NaiveDate::from_ymd(next_year, next_month, 1).pred().day()
+}
+
+// the day is clamped to the end of the month, so Jan 31 plus a month is Feb 28
+fn add_months(date: NaiveDate, months: i32) -> NaiveDate {
+  let total = date.year() * 12 + date.month0() as i32 + months;
+  let (year, month) = (total.div_euclid(12), total.rem_euclid(12) as u32 + 1);
+
+  NaiveDate::from_ymd(year, month, std::cmp::min(date.day(), days_in_month(year, month)))
+}
+
+// None if `to` is before `from`; someone born on Feb 29 gets a year older on Feb 28
+pub fn age_between(from: DateTime<UTC>, to: DateTime<UTC>) -> Option<Age> {
+  let (from, to) = (from.naive_utc().date(), to.naive_utc().date());
+
+  if to < from {
+    return None;
+  }
+
+  let mut months = (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32;
+
+  if add_months(from, months) > to {
+    months -= 1;
+  }
+
+  Some(Age {
+    years: months as u32 / 12,
+    months: months as u32 % 12,
+    days: (to - add_months(from, months)).num_days() as u32
+  })
+}
+
+// the age at the end, or on `reference` if there is none yet; None if
+// `reference` is needed and comes before the start
+pub fn age<T: Lifespan>(record: &T, reference: DateTime<UTC>) -> Option<Age> {
+  age_between(record.start(), record.end().unwrap_or(reference))
+}
+
+// only ended lifespans, None for open or reversed ones
+pub fn lifespan_days<T: Lifespan>(record: &T) -> Option<i64> {
+  record.end()
+    .map(|end| (end - record.start()).num_days())
+    .filter(|&days| days >= 0)
+}
+
+pub fn days_to_years(days: f64) -> f64 {
+  days / 365.2425
+}
+
+#[derive(Debug)]
+pub struct LifespanStats<'a, T: 'a> {
+  pub count: usize,
+  // the records the averages are taken over
+  pub ended: usize,
+  pub mean_days: Option<f64>,
+  pub median_days: Option<f64>,
+  pub longest: Option<&'a T>,
+  pub shortest: Option<&'a T>
+}
+
+impl<'a, T: Lifespan> LifespanStats<'a, T> {
+  pub fn new<I: IntoIterator<Item = &'a T>>(records: I) -> LifespanStats<'a, T> {
+    let mut count = 0;
+    let mut spans: Vec<(i64, &'a T)> = Vec::new();
+
+    for record in records {
+      count += 1;
+
+      if let Some(days) = lifespan_days(record) {
+        spans.push((days, record));
+      }
+    }
+
+    // stable, so the first record wins ties
+    spans.sort_by_key(|&(days, _)| days);
+
+    let median_days = match spans.len() {
+      0 => None,
+      len if len % 2 == 1 => Some(spans[len / 2].0 as f64),
+      len => Some((spans[len / 2 - 1].0 + spans[len / 2].0) as f64 / 2.0)
+    };
+
+    let mean_days = if spans.is_empty() {
+      None
+    } else {
+      Some(spans.iter().map(|&(days, _)| days as f64).sum::<f64>() / spans.len() as f64)
+    };
+
+    let max = spans.last().map(|&(days, _)| days);
+
+    LifespanStats {
+      count,
+      ended: spans.len(),
+      mean_days,
+      median_days,
+      longest: spans.iter().find(|&&(days, _)| Some(days) == max).map(|&(_, record)| record),
+      shortest: spans.first().map(|&(_, record)| record)
+    }
+  }
+}
+
+pub fn lifespan_stats<T: Lifespan>(records: &[T]) -> LifespanStats<'_, T> {
+  LifespanStats::new(records)
+}
+
+// the `n` longest ended lifespans, longest first
+pub fn longest_lifespans<T: Lifespan>(records: &[T], n: usize) -> Vec<(&T, Age)> {
+  let mut spans: Vec<(i64, &T)> = records.iter()
+    .filter_map(|record| lifespan_days(record).map(|days| (days, record)))
+    .collect();
+
+  spans.sort_by_key(|&(days, _)| -days);
+
+  spans.into_iter()
+    .take(n)
+    .filter_map(|(_, record)| record.end().and_then(|end| age_between(record.start(), end)).map(|age| (record, age)))
+    .collect()
+}
+
+// the oldest of the alive records after all events of each date, listed
+// whenever it changes; None while nobody is alive. Records that end before
+// they start are left out
+pub fn oldest_alive_timeline<T: Lifespan>(records: &[T]) -> Vec<(DateTime<UTC>, Option<&T>)> {
+  let mut events = Vec::new();
+
+  for (index, record) in records.iter().enumerate().filter(|&(_, record)| !is_reversed(record)) {
+    events.push((record.start(), EventType::Born, index));
+
+    if let Some(end) = record.end() {
+      events.push((end, EventType::Died, index));
+    }
+  }
+
+  events.sort_by_key(|&(date, _, _)| date);
+
+  let mut timeline = Vec::new();
+  // ordered by start, so the first one is the oldest
+  let mut alive = BTreeSet::new();
+  let mut shown = None;
+
+  for (i, &(date, event_type, index)) in events.iter().enumerate() {
+    let key = (records[index].start(), index);
+
+    match event_type {
+      EventType::Born => alive.insert(key),
+      EventType::Died => alive.remove(&key)
+    };
+
+    if events.get(i + 1).is_some_and(|next| next.0 == date) {
+      continue;
+    }
+
+    let oldest = alive.iter().next().map(|&(_, index)| index);
+
+    if shown != Some(oldest) {
+      timeline.push((date, oldest.map(|index| &records[index])));
+      shown = Some(oldest);
+    }
+  }
+
+  timeline
+}
+
+pub fn birth_decade<T: Lifespan>(record: &T) -> i32 {
+  record.start().year().div_euclid(10) * 10
+}
+
+// statistics per group, like `birth_decade` or an attribute of the records
+pub fn group_stats<T, K, F>(records: &[T], key: F) -> BTreeMap<K, LifespanStats<'_, T>>
+where T: Lifespan, K: Ord, F: Fn(&T) -> K {
+  let mut groups: BTreeMap<K, Vec<&T>> = BTreeMap::new();
+
+  for record in records {
+    groups.entry(key(record)).or_default().push(record);
+  }
+
+  groups.into_iter().map(|(key, group)| (key, LifespanStats::new(group))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  use chrono;
+  use chrono::offset::TimeZone;
+
+  use President;
+  use read_csv;
+
+  fn date(y: i32, m: u32, d: u32) -> DateTime<UTC> {
+    chrono::UTC.ymd(y, m, d).and_hms(0, 0, 0)
+  }
+
+  fn age_of(years: u32, months: u32, days: u32) -> Option<Age> {
+    Some(Age { years, months, days })
+  }
+
+  #[test]
+  fn test_age_between() {
+    assert_eq!(age_of(0, 0, 0), age_between(date(1900, 5, 5), date(1900, 5, 5)));
+    assert_eq!(age_of(67, 9, 22), age_between(date(1732, 2, 22), date(1799, 12, 14)));
+    assert_eq!(age_of(0, 1, 1), age_between(date(1900, 1, 31), date(1900, 3, 1)));
+    assert_eq!(age_of(0, 11, 30), age_between(date(1900, 1, 1), date(1900, 12, 31)));
+    assert_eq!(age_of(1, 0, 0), age_between(date(1904, 2, 29), date(1905, 2, 28)));
+    assert_eq!(age_of(4, 0, 0), age_between(date(1904, 2, 29), date(1908, 2, 29)));
+    assert_eq!(None, age_between(date(1900, 5, 5), date(1900, 5, 4)));
+
+    assert_eq!("67 years, 9 months, 22 days", age_between(date(1732, 2, 22), date(1799, 12, 14)).unwrap().to_string());
+  }
+
+  fn find<'a>(presidents: &'a [President], name: &str) -> &'a President {
+    presidents.iter().find(|p| p.get_name() == name).unwrap()
+  }
+
+  #[test]
+  fn test_age() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+    let reference = date(2016, 3, 1);
+
+    let washington = find(&presidents, "George Washington");
+    assert_eq!(age_of(67, 9, 22), age(washington, reference));
+    assert_eq!(Some(24767), lifespan_days(washington));
+
+    let carter = find(&presidents, "Jimmy Carter");
+    assert_eq!(None, lifespan_days(carter));
+    assert_eq!(age_of(91, 5, 0), age(carter, reference));
+    assert_eq!(None, age(carter, date(1900, 1, 1)));
+  }
+
+  #[test]
+  fn test_lifespan_stats() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+    let stats = lifespan_stats(&presidents);
+
+    assert_eq!(presidents.len(), stats.count);
+    assert_eq!(presidents.iter().filter(|p| p.end().is_some()).count(), stats.ended);
+    assert_eq!("Gerald Ford", stats.longest.unwrap().get_name());
+    assert_eq!("John F. Kennedy", stats.shortest.unwrap().get_name());
+
+    let mean = days_to_years(stats.mean_days.unwrap());
+    let median = days_to_years(stats.median_days.unwrap());
+    assert!(mean > 60.0 && mean < 80.0, "{}", mean);
+    assert!(median > 60.0 && median < 80.0, "{}", median);
+
+    let longest = longest_lifespans(&presidents, 3);
+    assert_eq!(3, longest.len());
+    assert_eq!("Gerald Ford", longest[0].0.get_name());
+    assert!(longest[0].1 >= longest[1].1 && longest[1].1 >= longest[2].1);
+
+    let empty: Vec<President> = Vec::new();
+    let stats = lifespan_stats(&empty);
+    assert_eq!((0, None, None), (stats.count, stats.mean_days, stats.longest.map(|p| p.get_name())));
+  }
+
+  #[test]
+  fn test_median() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+    let stats = LifespanStats::new(presidents.iter().take(4));
+
+    // 67.8, 90.7, 83.2 and 85.3 years
+    let days: Vec<i64> = presidents.iter().take(4).filter_map(lifespan_days).collect();
+    let mut sorted = days.clone();
+    sorted.sort();
+
+    assert_eq!(Some((sorted[1] + sorted[2]) as f64 / 2.0), stats.median_days);
+    assert_eq!(Some(days.iter().sum::<i64>() as f64 / 4.0), stats.mean_days);
+    assert_eq!("John Adams", stats.longest.unwrap().get_name());
+  }
+
+  #[test]
+  fn test_oldest_alive_timeline() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+    let timeline = oldest_alive_timeline(&presidents);
+
+    let names: Vec<(String, Option<&str>)> = timeline.iter()
+      .take(3)
+      .map(|&(date, oldest)| (date.format("%Y-%m-%d").to_string(), oldest.map(|p| p.get_name())))
+      .collect();
+
+    assert_eq!(vec![
+      ("1732-02-22".to_string(), Some("George Washington")),
+      ("1799-12-14".to_string(), Some("John Adams")),
+      ("1826-07-04".to_string(), Some("James Madison"))
+    ], names);
+
+    for window in timeline.windows(2) {
+      assert!(window[0].0 < window[1].0);
+      assert!(!std::ptr::eq(window[0].1.unwrap(), window[1].1.unwrap()));
+    }
+  }
+
+  struct Span {
+    from: DateTime<UTC>,
+    until: Option<DateTime<UTC>>
+  }
+
+  impl Lifespan for Span {
+    fn start(&self) -> DateTime<UTC> {
+      self.from
+    }
+
+    fn end(&self) -> Option<DateTime<UTC>> {
+      self.until
+    }
+  }
+
+  #[test]
+  fn test_oldest_alive_reversed() {
+    let spans = vec![
+      Span { from: date(1900, 1, 1), until: Some(date(1950, 1, 1)) },
+      // would never be removed again once born
+      Span { from: date(1890, 1, 1), until: Some(date(1880, 1, 1)) }
+    ];
+
+    let timeline = oldest_alive_timeline(&spans);
+
+    assert_eq!(2, timeline.len());
+    assert_eq!(date(1900, 1, 1), timeline[0].0);
+    assert!(std::ptr::eq(&spans[0], timeline[0].1.unwrap()));
+    assert_eq!(date(1950, 1, 1), timeline[1].0);
+    assert!(timeline[1].1.is_none());
+  }
+
+  #[test]
+  fn test_group_stats() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+
+    let decades = group_stats(&presidents, birth_decade);
+    assert_eq!(Some(&1730), decades.keys().next());
+    assert_eq!(2, decades[&1730].count);
+    assert_eq!(presidents.len(), decades.values().map(|stats| stats.count).sum::<usize>());
+
+    // the state is the last word of the birth place
+    let states = group_stats(&presidents, |p| p.get_birth_place().split_whitespace().last().unwrap_or("").to_string());
+    assert_eq!(7, states["Va."].count);
+    assert_eq!("James Madison", states["Va."].longest.map(|p| p.get_name()).unwrap_or(""));
+  }
+
+}
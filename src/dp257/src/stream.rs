@@ -0,0 +1,472 @@
+// stream.rs <weldale@gmail.com>
+//
+// The alive timeline for datasets that don't fit in memory. Records are read
+// once and turned into (timestamp, delta) pairs, which are sorted and spilled
+// to temporary files whenever the buffer reaches its budget. The sorted runs
+// are then merged back into the timeline one date at a time.
+
+use chrono::{
+  DateTime,
+  UTC
+};
+use chrono::offset::TimeZone;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::convert::TryFrom;
+use std::fs::{
+  File,
+  OpenOptions
+};
+use std::io::{
+  BufReader,
+  BufWriter,
+  ErrorKind,
+  Read,
+  Write
+};
+use std::path::{
+  Path,
+  PathBuf
+};
+use std::sync::atomic::{
+  AtomicUsize,
+  Ordering
+};
+use std::vec;
+
+use {
+  Lifespan,
+  is_reversed
+};
+
+// (seconds since the epoch, change of the alive count)
+type Pair = (i64, i64);
+
+const PAIR_SIZE: usize = 16;
+
+const DEFAULT_BUDGET: usize = 1 << 20;
+
+// tells the spill files of sweeps running at the same time apart
+static SWEEPS: AtomicUsize = AtomicUsize::new(0);
+
+pub struct SweepConfig {
+  budget: usize,
+  fan_in: usize,
+  dir: PathBuf
+}
+
+impl Default for SweepConfig {
+  fn default() -> SweepConfig {
+    SweepConfig::new()
+  }
+}
+
+impl SweepConfig {
+  pub fn new() -> SweepConfig {
+    SweepConfig {
+      budget: DEFAULT_BUDGET,
+      fan_in: 64,
+      dir: std::env::temp_dir()
+    }
+  }
+
+  // the most events kept in memory, 16 bytes each
+  pub fn budget(mut self, events: usize) -> Self {
+    self.budget = std::cmp::max(events, 1);
+    self
+  }
+
+  // the most runs merged at once, more runs are merged in several passes. At
+  // least 3, one of them is the buffer and a pass has to merge two runs
+  pub fn fan_in(mut self, runs: usize) -> Self {
+    self.fan_in = std::cmp::max(runs, 3);
+    self
+  }
+
+  pub fn dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+    self.dir = dir.as_ref().to_path_buf();
+    self
+  }
+}
+
+// removes the files once they aren't needed anymore, whatever happened
+struct SpillFiles {
+  paths: Vec<PathBuf>
+}
+
+impl Drop for SpillFiles {
+  fn drop(&mut self) {
+    for path in &self.paths {
+      let _ = std::fs::remove_file(path);
+    }
+  }
+}
+
+struct Spill {
+  dir: PathBuf,
+  sweep: usize,
+  count: usize,
+  files: SpillFiles
+}
+
+impl Spill {
+  fn new(dir: &Path) -> Spill {
+    Spill {
+      dir: dir.to_path_buf(),
+      sweep: SWEEPS.fetch_add(1, Ordering::SeqCst),
+      count: 0,
+      files: SpillFiles { paths: Vec::new() }
+    }
+  }
+
+  fn write<I: Iterator<Item = Result<Pair, String>>>(&mut self, pairs: I) -> Result<(), String> {
+    let path = self.dir.join(format!("dp257-{}-{}-{}.run", std::process::id(), self.sweep, self.count));
+    self.count += 1;
+
+    let file = OpenOptions::new().write(true).create_new(true).open(&path)
+      .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    // registered before writing so a half written run is removed as well
+    self.files.paths.push(path.clone());
+
+    let mut wtr = BufWriter::new(file);
+
+    for pair in pairs {
+      let (date, delta) = pair?;
+      let mut bytes = [0; PAIR_SIZE];
+      bytes[..8].copy_from_slice(&date.to_le_bytes());
+      bytes[8..].copy_from_slice(&delta.to_le_bytes());
+
+      wtr.write_all(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+
+    wtr.flush().map_err(|e| format!("{}: {}", path.display(), e))
+  }
+
+  // merges the oldest runs until at most `fan_in` are left
+  fn reduce(&mut self, fan_in: usize) -> Result<(), String> {
+    while self.files.paths.len() > fan_in {
+      let batch = SpillFiles { paths: self.files.paths.drain(..fan_in).collect() };
+      let mut merge = Merge::new(open_runs(&batch.paths)?)?;
+
+      self.write(std::iter::from_fn(|| merge.next().transpose()))?;
+    }
+
+    Ok(())
+  }
+}
+
+enum Run {
+  Memory(vec::IntoIter<Pair>),
+  File(PathBuf, BufReader<File>)
+}
+
+impl Run {
+  fn next(&mut self) -> Result<Option<Pair>, String> {
+    match *self {
+      Run::Memory(ref mut pairs) => Ok(pairs.next()),
+      Run::File(ref path, ref mut rdr) => {
+        let mut bytes = [0; PAIR_SIZE];
+
+        match rdr.read_exact(&mut bytes) {
+          Ok(()) => {
+            let mut date = [0; 8];
+            let mut delta = [0; 8];
+            date.copy_from_slice(&bytes[..8]);
+            delta.copy_from_slice(&bytes[8..]);
+
+            Ok(Some((i64::from_le_bytes(date), i64::from_le_bytes(delta))))
+          },
+          Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => Ok(None),
+          Err(e) => Err(format!("{}: {}", path.display(), e))
+        }
+      }
+    }
+  }
+}
+
+fn open_runs(paths: &[PathBuf]) -> Result<Vec<Run>, String> {
+  paths.iter()
+    .map(|path| File::open(path)
+      .map(|file| Run::File(path.clone(), BufReader::new(file)))
+      .map_err(|e| format!("{}: {}", path.display(), e)))
+    .collect()
+}
+
+// k-way merge of sorted runs, one date with the sum of its deltas at a time
+struct Merge {
+  runs: Vec<Run>,
+  // (date, run, delta), the run breaks ties so that runs are read in order
+  heap: BinaryHeap<Reverse<(i64, usize, i64)>>
+}
+
+impl Merge {
+  fn new(runs: Vec<Run>) -> Result<Merge, String> {
+    let mut merge = Merge {
+      heap: BinaryHeap::with_capacity(runs.len()),
+      runs
+    };
+
+    for run in 0..merge.runs.len() {
+      merge.refill(run)?;
+    }
+
+    Ok(merge)
+  }
+
+  fn refill(&mut self, run: usize) -> Result<(), String> {
+    if let Some((date, delta)) = self.runs[run].next()? {
+      self.heap.push(Reverse((date, run, delta)));
+    }
+
+    Ok(())
+  }
+
+  fn next(&mut self) -> Result<Option<Pair>, String> {
+    let Reverse((date, run, mut sum)) = match self.heap.pop() {
+      Some(first) => first,
+      None => return Ok(None)
+    };
+
+    self.refill(run)?;
+
+    while let Some(&Reverse((next, run, delta))) = self.heap.peek() {
+      if next != date {
+        break;
+      }
+
+      self.heap.pop();
+      sum += delta;
+      self.refill(run)?;
+    }
+
+    Ok(Some((date, sum)))
+  }
+}
+
+// sorts the pairs and sums the deltas of equal dates. A date whose births and
+// deaths cancel out is kept, `alive_timeline` lists it as well
+fn sort_run(pairs: &mut Vec<Pair>) {
+  pairs.sort_unstable_by_key(|&(date, _)| date);
+
+  // in place, the buffer is as big as memory allows
+  pairs.dedup_by(|next, kept| {
+    let same = next.0 == kept.0;
+
+    if same {
+      kept.1 += next.1;
+    }
+
+    same
+  });
+}
+
+// the alive timeline read back from the merged runs, the spill files are
+// removed when it's dropped
+pub struct MergedTimeline {
+  merge: Merge,
+  count: i64,
+  failed: bool,
+  files: SpillFiles
+}
+
+impl MergedTimeline {
+  // the number of runs merged from disk
+  pub fn spilled(&self) -> usize {
+    self.files.paths.len()
+  }
+}
+
+impl Iterator for MergedTimeline {
+  type Item = Result<(DateTime<UTC>, usize), String>;
+
+  fn next(&mut self) -> Option<Result<(DateTime<UTC>, usize), String>> {
+    if self.failed {
+      return None;
+    }
+
+    match self.merge.next() {
+      Ok(Some((date, delta))) => {
+        self.count += delta;
+
+        match usize::try_from(self.count) {
+          Ok(count) => Some(Ok((UTC.timestamp(date, 0), count))),
+          Err(_) => {
+            self.failed = true;
+            Some(Err(format!("more records ended than started on {}", UTC.timestamp(date, 0))))
+          }
+        }
+      },
+      Ok(None) => None,
+      Err(message) => {
+        self.failed = true;
+        Some(Err(message))
+      }
+    }
+  }
+}
+
+// the same timeline as `alive_timeline`, to the second, but holding at most
+// `config.budget` events in memory. The records are read exactly once, those
+// that end before they start are left out
+pub fn external_alive_timeline<I, T>(records: I, config: &SweepConfig) -> Result<MergedTimeline, String>
+where I: IntoIterator<Item = T>, T: Lifespan {
+  let mut spill = Spill::new(&config.dir);
+  // a record adds up to two events before the budget is checked. Budgets
+  // past the default grow the buffer as needed instead of reserving it all
+  let capacity = std::cmp::min(config.budget, DEFAULT_BUDGET).saturating_add(1);
+  let mut buffer: Vec<Pair> = Vec::with_capacity(capacity);
+
+  for record in records {
+    if is_reversed(&record) {
+      continue;
+    }
+
+    buffer.push((record.start().timestamp(), 1));
+
+    if let Some(end) = record.end() {
+      buffer.push((end.timestamp(), -1));
+    }
+
+    if buffer.len() >= config.budget {
+      sort_run(&mut buffer);
+      spill.write(buffer.iter().cloned().map(Ok))?;
+      buffer.clear();
+
+      spill.reduce(config.fan_in - 1)?;
+    }
+  }
+
+  let mut runs = open_runs(&spill.files.paths)?;
+  sort_run(&mut buffer);
+  runs.push(Run::Memory(buffer.into_iter()));
+
+  Ok(MergedTimeline {
+    merge: Merge::new(runs)?,
+    count: 0,
+    failed: false,
+    files: std::mem::replace(&mut spill.files, SpillFiles { paths: Vec::new() })
+  })
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  use chrono;
+  use chrono::Duration;
+
+  use alive_timeline;
+  use read_csv;
+
+  struct Span {
+    from: DateTime<UTC>,
+    until: Option<DateTime<UTC>>
+  }
+
+  impl Lifespan for Span {
+    fn start(&self) -> DateTime<UTC> {
+      self.from
+    }
+
+    fn end(&self) -> Option<DateTime<UTC>> {
+      self.until
+    }
+  }
+
+  // a directory of its own, so the spill files of a test can be counted
+  fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dp257-test-{}-{}", std::process::id(), name));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn collect(timeline: MergedTimeline) -> Vec<(DateTime<UTC>, usize)> {
+    timeline.collect::<Result<Vec<_>, String>>().unwrap()
+  }
+
+  #[test]
+  fn test_presidents() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+    let dir = test_dir("presidents");
+
+    let timeline = external_alive_timeline(&presidents, &SweepConfig::new().budget(3).fan_in(4).dir(&dir)).unwrap();
+    assert!(timeline.spilled() > 0);
+    assert!(timeline.spilled() < 4);
+    assert_eq!(alive_timeline(&presidents), collect(timeline));
+
+    // the smallest fan-in, 2 is raised to 3
+    for fan_in in 2..4 {
+      let timeline = external_alive_timeline(&presidents, &SweepConfig::new().budget(3).fan_in(fan_in).dir(&dir)).unwrap();
+      assert_eq!(2, timeline.spilled());
+      assert_eq!(alive_timeline(&presidents), collect(timeline));
+    }
+
+    let timeline = external_alive_timeline(&presidents, &SweepConfig::new().dir(&dir)).unwrap();
+    assert_eq!(0, timeline.spilled());
+    assert_eq!(alive_timeline(&presidents), collect(timeline));
+
+    let timeline = external_alive_timeline(&presidents, &SweepConfig::new().budget(usize::MAX).dir(&dir)).unwrap();
+    assert_eq!(0, timeline.spilled());
+    assert_eq!(alive_timeline(&presidents), collect(timeline));
+
+    assert_eq!(0, std::fs::read_dir(&dir).unwrap().count());
+    std::fs::remove_dir(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_many_spans() {
+    let epoch = chrono::UTC.ymd(1700, 1, 1).and_hms(0, 0, 0);
+
+    // lots of shared dates, and some spans that haven't ended
+    let spans: Vec<Span> = (0..100_000i64).map(|i| Span {
+      from: epoch + Duration::days(i * 7919 % 36_500),
+      until: if i % 10 == 0 { None } else { Some(epoch + Duration::days(i * 7919 % 36_500 + i % 29_000)) }
+    }).collect();
+
+    let dir = test_dir("many");
+    let timeline = external_alive_timeline(&spans, &SweepConfig::new().budget(1000).fan_in(8).dir(&dir)).unwrap();
+    assert_eq!(alive_timeline(&spans), collect(timeline));
+
+    assert_eq!(0, std::fs::read_dir(&dir).unwrap().count());
+    std::fs::remove_dir(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_reversed() {
+    let epoch = chrono::UTC.ymd(1800, 7, 4).and_hms(0, 0, 0);
+
+    let spans = vec![
+      Span { from: epoch, until: Some(epoch - Duration::days(3)) },
+      Span { from: epoch, until: Some(epoch + Duration::days(3)) }
+    ];
+
+    let dir = test_dir("reversed");
+    let timeline = collect(external_alive_timeline(&spans, &SweepConfig::new().budget(1).dir(&dir)).unwrap());
+
+    assert_eq!(vec![(epoch, 1), (epoch + Duration::days(3), 0)], timeline);
+    assert_eq!(alive_timeline(&spans), timeline);
+
+    std::fs::remove_dir(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_drop_early() {
+    let presidents = read_csv("data/presidents.csv").unwrap().presidents;
+    let dir = test_dir("drop");
+
+    let mut timeline = external_alive_timeline(&presidents, &SweepConfig::new().budget(10).dir(&dir)).unwrap();
+    assert_eq!(Some(Ok((presidents[0].start(), 1))), timeline.next());
+    assert!(std::fs::read_dir(&dir).unwrap().count() > 0);
+
+    drop(timeline);
+    assert_eq!(0, std::fs::read_dir(&dir).unwrap().count());
+    std::fs::remove_dir(&dir).unwrap();
+
+    let missing = SweepConfig::new().budget(1).dir(std::env::temp_dir().join("dp257-test-missing"));
+    assert!(external_alive_timeline(&presidents, &missing).is_err());
+  }
+
+}
@@ -1,5 +1,5 @@
 
-//pub mod dp255;
+pub mod dp255;
 pub mod dp253;
-//pub mod dp256;
+pub mod dp256;
 